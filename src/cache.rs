@@ -6,11 +6,11 @@
 
 use crate::{
     error::Result,
-    parser::{self, ParsedFile},
+    parser::{self, ParseOptions, ParsedFile},
 };
 use flate2::{Compression, read::GzDecoder, write::GzEncoder};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs,
     io::{Read, Write},
     path::{Path, PathBuf},
@@ -33,16 +33,19 @@ struct CacheEntry {
 struct Cache {
     /// Version of the cache format (for future compatibility)
     version: u32,
+    /// The parse options the cached results were produced with
+    parse_options: ParseOptions,
     /// Map from file path to cache entry
     entries: HashMap<PathBuf, CacheEntry>,
 }
 
 impl Cache {
-    const VERSION: u32 = 1;
+    const VERSION: u32 = 22;
 
-    fn new() -> Self {
+    fn new(parse_options: &ParseOptions) -> Self {
         Self {
             version: Self::VERSION,
+            parse_options: parse_options.clone(),
             entries: HashMap::new(),
         }
     }
@@ -107,64 +110,114 @@ pub fn parse_with_cache(
     root_dir: &Path,
     python_files: &[PathBuf],
 ) -> Result<Vec<Result<ParsedFile>>> {
-    let cache_path = get_cache_path(root_dir);
+    let roots = [(root_dir.to_path_buf(), python_files.to_vec())];
+    let mut results = parse_roots_with_cache(root_dir, &roots, &ParseOptions::default())?;
+    Ok(results.pop().unwrap_or_default())
+}
+
+/// Parses the Python files of several roots (source roots and library roots) with
+/// caching enabled.
+///
+/// Each file's module path is computed relative to the root it is listed under. All
+/// results are stored in the cache of the project root, since library roots (e.g. a
+/// `site-packages` directory) are treated as read-only. Entries for files which aren't
+/// listed under any of the roots are dropped from the cache.
+///
+/// # Arguments
+///
+/// * `project_root` - The project root directory (used for cache location)
+/// * `roots` - The roots, each with the list of Python files to parse within it
+/// * `options` - Options controlling how the files are parsed
+///
+/// # Returns
+///
+/// The parse results of each root, in the same order as `roots`.
+///
+/// Cached results are only reused if they were produced with the same parse options and
+/// have the same module path, since the same file may be reached from different roots.
+pub fn parse_roots_with_cache(
+    project_root: &Path,
+    roots: &[(PathBuf, Vec<PathBuf>)],
+    options: &ParseOptions,
+) -> Result<Vec<Vec<Result<ParsedFile>>>> {
+    let cache_path = get_cache_path(project_root);
 
     // Load existing cache
-    let mut cache = Cache::load(&cache_path).unwrap_or_else(Cache::new);
+    let mut cache = Cache::load(&cache_path).unwrap_or_else(|| Cache::new(options));
 
-    // Check if cache version matches
-    if cache.version != Cache::VERSION {
-        cache = Cache::new();
+    // Check if cache version and parse options match
+    if cache.version != Cache::VERSION || cache.parse_options != *options {
+        cache = Cache::new(options);
     }
 
-    let mut results = Vec::with_capacity(python_files.len());
-    let mut files_to_parse = Vec::new();
+    let mut all_results = Vec::with_capacity(roots.len());
     let mut cache_hits = 0;
     let mut cache_misses = 0;
 
-    // First pass: check cache
-    for file_path in python_files {
-        if let Some((mtime, size)) = get_file_metadata(file_path)
-            && let Some(entry) = cache.entries.get(file_path)
-        {
-            // Check if file has changed
-            if entry.mtime == mtime && entry.size == size {
-                // Cache hit
-                results.push(Ok(entry.parsed.clone()));
-                cache_hits += 1;
-                continue;
-            }
-        }
+    for (root_dir, python_files) in roots {
+        let mut results = Vec::with_capacity(python_files.len());
+        let mut files_to_parse = Vec::new();
 
-        // Cache miss - need to parse
-        files_to_parse.push(file_path.clone());
-        cache_misses += 1;
-    }
+        // First pass: check cache
+        for file_path in python_files {
+            if let Some((mtime, size)) = get_file_metadata(file_path)
+                && let Some(entry) = cache.entries.get(file_path)
+            {
+                // Check if file has changed
+                if entry.mtime == mtime
+                    && entry.size == size
+                    && parser::file_path_to_module_path(file_path, root_dir).as_ref()
+                        == Some(&entry.parsed.module_path)
+                {
+                    // Cache hit
+                    results.push(Ok(entry.parsed.clone()));
+                    cache_hits += 1;
+                    continue;
+                }
+            }
 
-    // Parse files that weren't in cache or have changed
-    if !files_to_parse.is_empty() {
-        let parse_results = parser::parse_files(root_dir, &files_to_parse)?;
+            // Cache miss - need to parse
+            files_to_parse.push(file_path.clone());
+            cache_misses += 1;
+        }
 
-        // Update cache and collect results
-        for parse_result in parse_results {
-            if let Ok(parsed) = &parse_result
-                && let Some((mtime, size)) = get_file_metadata(&parsed.file_path)
-            {
-                cache.entries.insert(
-                    parsed.file_path.clone(),
-                    CacheEntry {
-                        mtime,
-                        size,
-                        parsed: parsed.clone(),
-                    },
-                );
+        // Parse files that weren't in cache or have changed
+        if !files_to_parse.is_empty() {
+            let parse_results =
+                parser::parse_files_with_options(root_dir, &files_to_parse, options)?;
+
+            // Update cache and collect results
+            for parse_result in parse_results {
+                if let Ok(parsed) = &parse_result
+                    && let Some((mtime, size)) = get_file_metadata(&parsed.file_path)
+                {
+                    cache.entries.insert(
+                        parsed.file_path.clone(),
+                        CacheEntry {
+                            mtime,
+                            size,
+                            parsed: parsed.clone(),
+                        },
+                    );
+                }
+                results.push(parse_result);
             }
-            results.push(parse_result);
         }
+
+        all_results.push(results);
     }
 
+    // Drop the entries of files which weren't part of this run (e.g. deleted files, or
+    // the files of a library root which is no longer searched)
+    let entry_count = cache.entries.len();
+    let run_files: HashSet<&PathBuf> = roots.iter().flat_map(|(_, files)| files).collect();
+    cache
+        .entries
+        .retain(|file_path, _| run_files.contains(file_path));
+    let pruned = cache.entries.len() < entry_count;
+
     // Save updated cache
-    if cache_misses > 0
+    if (cache_misses > 0 || pruned)
         && let Err(e) = cache.save(&cache_path)
     {
         log::warn!("Failed to save cache: {e}");
@@ -174,5 +227,5 @@ pub fn parse_with_cache(
         log::info!("Cache: {cache_hits} hits, {cache_misses} misses");
     }
 
-    Ok(results)
+    Ok(all_results)
 }
@@ -0,0 +1,389 @@
+//! Classification of classes by kind.
+//!
+//! Classes are tagged as abstract, concrete, mixin, protocol, enum, dataclass and/or
+//! exception, based on their bases, decorators, members and metaclass, and those of the
+//! classes they inherit from. Names which don't resolve to a class in the codebase (such
+//! as `enum.Enum` or `dataclasses.dataclass`) are recognised through the imports of the
+//! module using them.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::graph::InheritanceGraph;
+use crate::parser::BaseClass;
+use crate::registry::{self, ClassId, Registry};
+
+/// A kind of class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ClassTag {
+    /// The class can't be instantiated: it has abstract methods, or inherits directly from
+    /// `abc.ABC` or declares the `abc.ABCMeta` metaclass
+    Abstract,
+    /// The class isn't abstract, a protocol or a mixin
+    Concrete,
+    /// The class is a mixin: its name ends with "Mixin", or it has no bases and is only
+    /// ever combined with other bases by its subclasses
+    Mixin,
+    /// The class is a protocol, i.e. inherits directly from `typing.Protocol`
+    Protocol,
+    /// The class is an enum, i.e. inherits from `enum.Enum` (or `IntEnum`, `Flag`, etc.)
+    Enum,
+    /// The class is (or inherits from) a dataclass, an attrs class or a pydantic model
+    Dataclass,
+    /// The class inherits from `BaseException`
+    Exception,
+}
+
+impl fmt::Display for ClassTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ClassTag::Abstract => "abstract",
+            ClassTag::Concrete => "concrete",
+            ClassTag::Mixin => "mixin",
+            ClassTag::Protocol => "protocol",
+            ClassTag::Enum => "enum",
+            ClassTag::Dataclass => "dataclass",
+            ClassTag::Exception => "exception",
+        })
+    }
+}
+
+/// An abstract method (a method decorated with `@abstractmethod`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbstractMethod {
+    /// The name of the method
+    pub name: String,
+    /// The qualified name of the class declaring the method abstract (e.g. "shapes.Shape")
+    pub declared_in: String,
+}
+
+/// The enum base classes of the `enum` module, with the enum classes each derives from
+/// (itself included).
+const ENUM_BASES: &[(&str, &[&str])] = &[
+    ("Enum", &["Enum"]),
+    ("ReprEnum", &["ReprEnum", "Enum"]),
+    ("IntEnum", &["IntEnum", "ReprEnum", "Enum"]),
+    ("StrEnum", &["StrEnum", "ReprEnum", "Enum"]),
+    ("Flag", &["Flag", "Enum"]),
+    ("IntFlag", &["IntFlag", "ReprEnum", "Flag", "Enum"]),
+];
+
+/// The qualified names of `Protocol`.
+const PROTOCOL_BASES: &[&str] = &["typing.Protocol", "typing_extensions.Protocol"];
+
+/// The qualified names of `NamedTuple`.
+const NAMED_TUPLE_BASES: &[&str] = &["typing.NamedTuple", "typing_extensions.NamedTuple"];
+
+/// The qualified names of `TypedDict`.
+const TYPED_DICT_BASES: &[&str] = &["typing.TypedDict", "typing_extensions.TypedDict"];
+
+/// The qualified name of the pydantic model base class.
+const PYDANTIC_MODEL_BASE: &str = "pydantic.BaseModel";
+
+/// The metaclasses of enums in the `enum` module.
+const ENUM_METACLASSES: &[&str] = &["EnumMeta", "EnumType"];
+
+/// The attrs class decorators (e.g. `@attr.s` or `@attrs.define`).
+const ATTRS_DECORATORS: &[&str] = &["s", "attrs", "define", "frozen", "mutable", "dataclass"];
+
+/// Returns whether a base class of the class `id` is `typing.Protocol` (or
+/// `typing_extensions.Protocol`), as opposed to e.g. `asyncio.Protocol`.
+pub fn is_protocol_base(registry: &Registry, id: &ClassId, base: &BaseClass) -> bool {
+    qualify_external_base(registry, id, &base.name)
+        .is_some_and(|base| PROTOCOL_BASES.contains(&base.as_str()))
+}
+
+/// Returns whether a class is a protocol, i.e. lists `typing.Protocol` as a base.
+pub fn is_protocol(registry: &Registry, id: &ClassId) -> bool {
+    registry.classes.get(id).is_some_and(|class| {
+        class
+            .bases
+            .iter()
+            .any(|base| is_protocol_base(registry, id, base))
+    })
+}
+
+/// Returns whether a name refers to an enum base class of the `enum` module, bare or
+/// qualified (e.g. "IntEnum" or "enum.IntEnum").
+pub fn is_enum_base(name: &str) -> bool {
+    enum_base_ancestors(&format!(
+        "enum.{}",
+        name.strip_prefix("enum.").unwrap_or(name)
+    ))
+    .is_some()
+}
+
+/// Returns the enum classes an enum base class of the `enum` module derives from, given
+/// its qualified name (e.g. "enum.IntEnum"), or `None` if the name isn't one of them.
+fn enum_base_ancestors(qualified_name: &str) -> Option<&'static [&'static str]> {
+    let name = qualified_name.strip_prefix("enum.")?;
+    ENUM_BASES
+        .iter()
+        .find(|(base, _)| *base == name)
+        .map(|(_, ancestors)| *ancestors)
+}
+
+/// Returns the last component of a dotted name (e.g. "Enum" for "enum.Enum").
+fn last_component(name: &str) -> &str {
+    name.rsplit('.').next().unwrap_or(name)
+}
+
+/// Returns the abstract methods of a class, i.e. the methods whose first definition in
+/// its MRO is abstract.
+pub fn abstract_methods(
+    registry: &Registry,
+    graph: &InheritanceGraph,
+    id: &ClassId,
+) -> Vec<AbstractMethod> {
+    let mut seen = HashSet::new();
+    let mut methods = Vec::new();
+    for class_id in graph.mro(id) {
+        let Some(class) = registry.classes.get(&class_id) else {
+            continue;
+        };
+        for member in &class.members {
+            if seen.insert(member.name.as_str()) && member.is_abstract() {
+                methods.push(AbstractMethod {
+                    name: member.name.clone(),
+                    declared_in: format!("{}.{}", class_id.module, class_id.name),
+                });
+            }
+        }
+    }
+    methods
+}
+
+/// Returns whether a class is declared to be abstract: it declares abstract methods
+/// itself, lists `abc.ABC` or `typing.Protocol` as a base, or has the `abc.ABCMeta`
+/// metaclass.
+pub fn is_declared_abstract(registry: &Registry, id: &ClassId) -> bool {
+    let Some(class) = registry.classes.get(id) else {
+        return false;
+    };
+    class.members.iter().any(|member| member.is_abstract())
+        || class.bases.iter().any(|base| {
+            qualify_external_base(registry, id, &base.name).as_deref() == Some("abc.ABC")
+                || is_protocol_base(registry, id, base)
+        })
+        || class
+            .metaclass
+            .as_deref()
+            .is_some_and(|metaclass| registry.qualify_imported_name(id, metaclass) == "abc.ABCMeta")
+}
+
+/// Returns the enum base classes of the `enum` module a class derives from, directly or
+/// through its parent classes (e.g. `["IntEnum", "ReprEnum", "Enum"]`).
+///
+/// Classes declaring the `EnumMeta` metaclass (or inheriting it) derive from `Enum`. The
+/// result is empty if the class isn't an enum.
+pub fn enum_bases(
+    registry: &Registry,
+    graph: &InheritanceGraph,
+    id: &ClassId,
+) -> Vec<&'static str> {
+    let mut bases = Vec::new();
+    for class_id in lineage(graph, id) {
+        bases.extend(
+            external_bases(registry, std::slice::from_ref(&class_id))
+                .iter()
+                .filter_map(|base| enum_base_ancestors(base))
+                .flatten(),
+        );
+        if has_enum_metaclass(registry, &class_id) {
+            bases.push("Enum");
+        }
+    }
+
+    let mut seen = HashSet::new();
+    bases.retain(|base| seen.insert(*base));
+    bases
+}
+
+/// Returns whether a class declares the `enum.EnumMeta` metaclass.
+fn has_enum_metaclass(registry: &Registry, id: &ClassId) -> bool {
+    registry
+        .classes
+        .get(id)
+        .and_then(|metadata| metadata.metaclass.as_deref())
+        .is_some_and(|metaclass| {
+            registry
+                .qualify_imported_name(id, metaclass)
+                .strip_prefix("enum.")
+                .is_some_and(|metaclass| ENUM_METACLASSES.contains(&metaclass))
+        })
+}
+
+/// Classifies a class.
+///
+/// This walks the ancestry of the class, so it is meant to be called for the classes
+/// being reported rather than for every class in the codebase.
+///
+/// # Returns
+///
+/// The tags of the class, sorted. Every class is tagged either abstract, protocol, mixin
+/// or concrete.
+pub fn classify(registry: &Registry, graph: &InheritanceGraph, id: &ClassId) -> Vec<ClassTag> {
+    if !registry.classes.contains_key(id) {
+        return Vec::new();
+    }
+    let mut tags = Vec::new();
+
+    // The bases of the class and its parents which don't resolve to classes in the
+    // codebase, and their decorators, qualified by their imports
+    let lineage = lineage(graph, id);
+    let external_bases = external_bases(registry, &lineage);
+    let decorators: Vec<String> = lineage
+        .iter()
+        .flat_map(|class_id| qualified_decorators(registry, class_id))
+        .collect();
+
+    let is_protocol = is_protocol(registry, id);
+    if is_protocol {
+        tags.push(ClassTag::Protocol);
+    }
+
+    let is_abstract = !is_protocol
+        && (is_declared_abstract(registry, id)
+            || !abstract_methods(registry, graph, id).is_empty());
+    if is_abstract {
+        tags.push(ClassTag::Abstract);
+    }
+
+    let is_mixin = !is_protocol && !is_abstract && is_mixin(registry, graph, id);
+    if is_mixin {
+        tags.push(ClassTag::Mixin);
+    }
+
+    if !is_protocol && !is_abstract && !is_mixin {
+        tags.push(ClassTag::Concrete);
+    }
+
+    if external_bases
+        .iter()
+        .any(|base| enum_base_ancestors(base).is_some())
+        || lineage
+            .iter()
+            .any(|class_id| has_enum_metaclass(registry, class_id))
+    {
+        tags.push(ClassTag::Enum);
+    }
+
+    if decorators
+        .iter()
+        .any(|decorator| is_dataclass_decorator(decorator))
+        || external_bases
+            .iter()
+            .any(|base| base == PYDANTIC_MODEL_BASE)
+    {
+        tags.push(ClassTag::Dataclass);
+    }
+
+    // Exceptions derive from `BaseException`, which is an external class
+    if lineage.iter().any(registry::is_builtin_exception) {
+        tags.push(ClassTag::Exception);
+    }
+
+    tags.sort();
+    tags
+}
+
+/// Returns whether the annotated attributes of a class are fields.
+///
+/// This is the case for dataclasses and attrs classes (the class itself must be
+/// decorated), `NamedTuple`s, and classes deriving from a pydantic model or `TypedDict`.
+pub fn declares_fields(registry: &Registry, graph: &InheritanceGraph, id: &ClassId) -> bool {
+    let Some(class) = registry.classes.get(id) else {
+        return false;
+    };
+
+    qualified_decorators(registry, id).any(|decorator| is_dataclass_decorator(&decorator))
+        || class.bases.iter().any(|base| {
+            qualify_external_base(registry, id, &base.name)
+                .is_some_and(|base| NAMED_TUPLE_BASES.contains(&base.as_str()))
+        })
+        || external_bases(registry, &lineage(graph, id))
+            .iter()
+            .any(|base| base == PYDANTIC_MODEL_BASE || TYPED_DICT_BASES.contains(&base.as_str()))
+}
+
+/// Returns a class followed by all of its parent classes.
+fn lineage(graph: &InheritanceGraph, id: &ClassId) -> Vec<ClassId> {
+    std::iter::once(id.clone())
+        .chain(graph.find_all_parent_classes(id))
+        .collect()
+}
+
+/// Returns the bases of the given classes which don't resolve to classes in the
+/// codebase, qualified by their imports (e.g. "enum.Enum").
+fn external_bases(registry: &Registry, class_ids: &[ClassId]) -> Vec<String> {
+    let mut external_bases = Vec::new();
+    for class_id in class_ids {
+        let Some(metadata) = registry.classes.get(class_id) else {
+            continue;
+        };
+        external_bases.extend(
+            metadata
+                .bases
+                .iter()
+                .filter_map(|base| qualify_external_base(registry, class_id, &base.name)),
+        );
+    }
+    external_bases
+}
+
+/// Qualifies a base class name used in the definition of the class `id` with the import
+/// it refers to (e.g. "enum.Enum"), or returns `None` if it resolves to a class in the
+/// codebase.
+fn qualify_external_base(registry: &Registry, id: &ClassId, name: &str) -> Option<String> {
+    registry
+        .resolve_base_class(id, name)
+        .is_none()
+        .then(|| registry.qualify_imported_name(id, name))
+}
+
+/// Returns the decorators of a class, qualified by their imports
+/// (e.g. "dataclasses.dataclass").
+fn qualified_decorators<'a>(
+    registry: &'a Registry,
+    id: &'a ClassId,
+) -> impl Iterator<Item = String> + 'a {
+    registry
+        .classes
+        .get(id)
+        .into_iter()
+        .flat_map(|metadata| &metadata.decorators)
+        .map(|decorator| registry.qualify_imported_name(id, &decorator.name))
+}
+
+/// Returns whether a class is a mixin: its name ends with "Mixin", or it has no bases
+/// and is only ever used alongside other bases by its subclasses.
+fn is_mixin(registry: &Registry, graph: &InheritanceGraph, id: &ClassId) -> bool {
+    if last_component(&id.name).ends_with("Mixin") {
+        return true;
+    }
+
+    let Some(class) = registry.classes.get(id) else {
+        return false;
+    };
+    if class.bases.iter().any(|base| base.name != "object") {
+        return false;
+    }
+
+    let children = graph.find_direct_subclasses(id);
+    !children.is_empty()
+        && children.iter().all(|child| {
+            registry
+                .classes
+                .get(child)
+                .is_some_and(|child| child.bases.len() > 1)
+        })
+}
+
+/// Returns whether a (qualified) decorator creates a dataclass, an attrs class or a
+/// pydantic dataclass.
+fn is_dataclass_decorator(decorator: &str) -> bool {
+    let first_component = decorator.split('.').next().unwrap_or(decorator);
+    last_component(decorator) == "dataclass"
+        || (matches!(first_component, "attr" | "attrs")
+            && ATTRS_DECORATORS.contains(&last_component(decorator)))
+}
@@ -1,11 +1,35 @@
 //! File discovery module for finding Python files in a directory tree.
 
-use ignore::Walk;
-use std::path::{Path, PathBuf};
+use ignore::{Walk, WalkBuilder};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
 
-use crate::error::Result;
+use crate::{
+    error::{Error, Result},
+    notebook::is_notebook_file,
+    parser::is_stub_file,
+};
 
-/// Discovers all Python files in a directory tree.
+/// Which file to index when a module has both a source file (`.py`) and a stub file
+/// (`.pyi`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StubPolicy {
+    /// Index only the stub file
+    PreferStub,
+    /// Index only the source file
+    PreferSource,
+    /// Index both files, merging their class definitions
+    ///
+    /// Classes defined in both files are reported from the stub file.
+    #[default]
+    Merge,
+}
+
+/// Discovers all Python files (`.py` sources, `.pyi` stubs and `.ipynb` notebooks) in a
+/// directory tree.
 ///
 /// Uses the `ignore` crate to respect `.gitignore` files and other VCS ignore patterns.
 /// This automatically skips common directories like `__pycache__`, `.venv`, etc. if they
@@ -17,7 +41,7 @@ use crate::error::Result;
 ///
 /// # Returns
 ///
-/// A vector of paths to all `.py` files found in the directory tree.
+/// A vector of paths to all `.py`, `.pyi` and `.ipynb` files found in the directory tree.
 pub fn discover_python_files(root_dir: &Path) -> Result<Vec<PathBuf>> {
     discover_python_files_with_exclusions(root_dir, &[])
 }
@@ -34,7 +58,7 @@ pub fn discover_python_files(root_dir: &Path) -> Result<Vec<PathBuf>> {
 ///
 /// # Returns
 ///
-/// A vector of paths to all `.py` files found in the directory tree.
+/// A vector of paths to all `.py`, `.pyi` and `.ipynb` files found in the directory tree.
 pub fn discover_python_files_with_exclusions(
     root_dir: &Path,
     exclude_dirs: &[PathBuf],
@@ -67,11 +91,49 @@ pub fn discover_python_files_with_exclusions(
             continue;
         }
 
-        // Only include files (not directories) with .py extension
+        // Only include files (not directories) with .py, .pyi or .ipynb extension
+        if entry.file_type().is_some_and(|ft| ft.is_file())
+            && path.extension().is_some_and(|s| {
+                s.to_str()
+                    .is_some_and(|s| s == "py" || s == "pyi" || s == "ipynb")
+            })
+        {
+            python_files.push(path.to_path_buf());
+        }
+    }
+
+    Ok(python_files)
+}
+
+/// Discovers the Python files (`.py` sources and `.pyi` stubs) in a library root, such as
+/// a `site-packages` directory.
+///
+/// Unlike [`discover_python_files`], ignore files and hidden directories are not taken
+/// into account, since installed packages are never checked in. `__pycache__`
+/// directories are skipped, and notebooks are not indexed.
+///
+/// # Arguments
+///
+/// * `library_root` - The library root to search
+///
+/// # Returns
+///
+/// A vector of paths to all `.py` and `.pyi` files found in the library root.
+pub fn discover_library_files(library_root: &Path) -> Result<Vec<PathBuf>> {
+    let mut python_files = Vec::new();
+
+    let walk = WalkBuilder::new(library_root)
+        .standard_filters(false)
+        .filter_entry(|entry| entry.file_name() != "__pycache__")
+        .build();
+    for entry in walk {
+        let entry = entry.map_err(std::io::Error::other)?;
+        let path = entry.path();
+
         if entry.file_type().is_some_and(|ft| ft.is_file())
             && path
                 .extension()
-                .is_some_and(|s| s.to_str().is_some_and(|s| s == "py"))
+                .is_some_and(|s| s.to_str().is_some_and(|s| s == "py" || s == "pyi"))
         {
             python_files.push(path.to_path_buf());
         }
@@ -79,3 +141,106 @@ pub fn discover_python_files_with_exclusions(
 
     Ok(python_files)
 }
+
+/// Returns the root a file's module path is relative to: the deepest of `roots`
+/// containing the file, or `None` if none of them do.
+///
+/// E.g. `services/api/src` rather than the project root for
+/// `services/api/src/api/views.py`.
+pub fn owning_root<'a>(file_path: &Path, roots: &'a [PathBuf]) -> Option<&'a Path> {
+    roots
+        .iter()
+        .filter(|root| file_path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+        .map(PathBuf::as_path)
+}
+
+/// Finds the `site-packages` directories of a Python environment (e.g. a virtualenv).
+///
+/// Looks for `lib/python3.X/site-packages` (and `Lib/site-packages` on Windows). If the
+/// path is itself a `site-packages` directory, it is returned as is.
+///
+/// # Arguments
+///
+/// * `environment` - The path of the Python environment (e.g. `.venv`)
+///
+/// # Errors
+///
+/// Returns [`Error::SitePackagesNotFound`] if the environment has no `site-packages`
+/// directory.
+pub fn find_site_packages(environment: &Path) -> Result<Vec<PathBuf>> {
+    if environment
+        .file_name()
+        .is_some_and(|name| name == "site-packages")
+    {
+        return Ok(vec![environment.to_path_buf()]);
+    }
+
+    let mut site_packages = Vec::new();
+    for lib in ["lib", "lib64"] {
+        let Ok(entries) = fs::read_dir(environment.join(lib)) else {
+            continue;
+        };
+        let mut python_dirs: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("python"))
+            .map(|entry| entry.path().join("site-packages"))
+            .filter(|path| path.is_dir())
+            .collect();
+        python_dirs.sort();
+        site_packages.extend(python_dirs);
+    }
+    let windows_site_packages = environment.join("Lib").join("site-packages");
+    if windows_site_packages.is_dir() {
+        site_packages.push(windows_site_packages);
+    }
+
+    // `lib64` is often a symlink to `lib`
+    let mut seen = HashSet::new();
+    site_packages.retain(|path| seen.insert(path.canonicalize().unwrap_or_else(|_| path.clone())));
+
+    if site_packages.is_empty() {
+        return Err(Error::SitePackagesNotFound {
+            path: environment.to_path_buf(),
+        });
+    }
+    Ok(site_packages)
+}
+
+/// Applies a [`StubPolicy`] to a list of discovered Python files.
+///
+/// A source file and a stub file belong to the same module if they only differ by their
+/// extension (e.g. `foo/bar.py` and `foo/bar.pyi`). Files without a sibling, and notebooks,
+/// are always kept.
+///
+/// # Arguments
+///
+/// * `python_files` - The discovered Python files
+/// * `policy` - Which file to keep when a module has both
+///
+/// # Returns
+///
+/// The files to index, in their original order.
+pub fn apply_stub_policy(python_files: Vec<PathBuf>, policy: StubPolicy) -> Vec<PathBuf> {
+    if policy == StubPolicy::Merge {
+        return python_files;
+    }
+
+    let files: HashSet<PathBuf> = python_files.iter().cloned().collect();
+    python_files
+        .into_iter()
+        .filter(|path| {
+            if is_notebook_file(path) {
+                return true;
+            }
+            let is_stub = is_stub_file(path);
+            let sibling = path.with_extension(if is_stub { "py" } else { "pyi" });
+            let has_sibling = files.contains(&sibling);
+            match policy {
+                StubPolicy::PreferStub => is_stub || !has_sibling,
+                StubPolicy::PreferSource => !is_stub || !has_sibling,
+                StubPolicy::Merge => true,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,171 @@
+//! Source file decoding.
+//!
+//! Python source files are UTF-8 by default, but may start with a UTF-8 byte order mark,
+//! or declare a different encoding with a PEP 263 coding cookie
+//! (e.g. `# -*- coding: latin-1 -*-`) on their first or second line.
+
+use std::fs;
+use std::path::Path;
+
+use crate::error::{Error, Result};
+
+/// The UTF-8 byte order mark.
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// The characters cp1252 maps the bytes 0x80-0x9F to (`None` for undefined bytes).
+/// The remaining bytes map to the same code points as in Latin-1.
+const CP1252_HIGH: [Option<char>; 32] = [
+    Some('\u{20AC}'),
+    None,
+    Some('\u{201A}'),
+    Some('\u{0192}'),
+    Some('\u{201E}'),
+    Some('\u{2026}'),
+    Some('\u{2020}'),
+    Some('\u{2021}'),
+    Some('\u{02C6}'),
+    Some('\u{2030}'),
+    Some('\u{0160}'),
+    Some('\u{2039}'),
+    Some('\u{0152}'),
+    None,
+    Some('\u{017D}'),
+    None,
+    None,
+    Some('\u{2018}'),
+    Some('\u{2019}'),
+    Some('\u{201C}'),
+    Some('\u{201D}'),
+    Some('\u{2022}'),
+    Some('\u{2013}'),
+    Some('\u{2014}'),
+    Some('\u{02DC}'),
+    Some('\u{2122}'),
+    Some('\u{0161}'),
+    Some('\u{203A}'),
+    Some('\u{0153}'),
+    None,
+    Some('\u{017E}'),
+    Some('\u{0178}'),
+];
+
+/// The encodings that source files can be decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Latin1,
+    Cp1252,
+    Ascii,
+}
+
+impl Encoding {
+    /// Looks up an encoding by the name used in a coding cookie.
+    ///
+    /// Names are normalised the way Python does (case-insensitive, `_` equivalent to
+    /// `-`, and e.g. "utf-8-sig" treated as "utf-8").
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase().replace('_', "-");
+        let is = |canonical: &str| name == canonical || name.starts_with(&format!("{canonical}-"));
+        if is("utf-8") || name == "utf8" {
+            Some(Encoding::Utf8)
+        } else if is("latin-1") || is("iso-8859-1") || is("iso-latin-1") || name == "latin1" {
+            Some(Encoding::Latin1)
+        } else if name == "cp1252" || name == "windows-1252" {
+            Some(Encoding::Cp1252)
+        } else if name == "ascii" || name == "us-ascii" {
+            Some(Encoding::Ascii)
+        } else {
+            None
+        }
+    }
+
+    /// Decodes bytes, returning `None` if they aren't valid in this encoding.
+    fn decode(self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Encoding::Utf8 => String::from_utf8(bytes.to_vec()).ok(),
+            Encoding::Latin1 => Some(bytes.iter().map(|&b| b as char).collect()),
+            Encoding::Cp1252 => bytes
+                .iter()
+                .map(|&b| match b {
+                    0x80..=0x9F => CP1252_HIGH[(b - 0x80) as usize],
+                    _ => Some(b as char),
+                })
+                .collect(),
+            Encoding::Ascii => bytes
+                .is_ascii()
+                .then(|| String::from_utf8_lossy(bytes).into_owned()),
+        }
+    }
+}
+
+/// Reads a Python source file, decoding it according to its byte order mark or
+/// coding cookie.
+///
+/// Files declaring an encoding that isn't supported are decoded as UTF-8.
+///
+/// # Errors
+///
+/// Returns [`Error::DecodeError`] if the file can't be decoded, or [`Error::IoError`]
+/// if it can't be read.
+pub fn read_source(file_path: &Path) -> Result<String> {
+    let bytes = fs::read(file_path)?;
+
+    let (encoding_name, encoding, bytes) = if let Some(bytes) = bytes.strip_prefix(UTF8_BOM) {
+        ("utf-8".to_string(), Encoding::Utf8, bytes)
+    } else {
+        match coding_cookie(&bytes) {
+            Some(name) => match Encoding::from_name(&name) {
+                Some(encoding) => (name, encoding, &bytes[..]),
+                None => {
+                    log::debug!(
+                        "Unsupported encoding '{name}' declared in {}, decoding as utf-8",
+                        file_path.display()
+                    );
+                    ("utf-8".to_string(), Encoding::Utf8, &bytes[..])
+                }
+            },
+            None => ("utf-8".to_string(), Encoding::Utf8, &bytes[..]),
+        }
+    };
+
+    encoding.decode(bytes).ok_or_else(|| Error::DecodeError {
+        file: file_path.to_path_buf(),
+        encoding: encoding_name,
+    })
+}
+
+/// Returns the encoding declared by a PEP 263 coding cookie, if any.
+///
+/// The cookie must be in a comment on the first or second line, matching
+/// `coding[:=]\s*([-\w.]+)`. A cookie on the second line only counts if the first line
+/// is a comment or blank.
+fn coding_cookie(bytes: &[u8]) -> Option<String> {
+    let mut lines = bytes.split(|&b| b == b'\n');
+    let first = lines.next()?;
+    if let Some(name) = line_coding_cookie(first) {
+        return Some(name);
+    }
+    let first = first.trim_ascii();
+    if !first.is_empty() && !first.starts_with(b"#") {
+        return None;
+    }
+    line_coding_cookie(lines.next()?)
+}
+
+/// Returns the encoding declared by a coding cookie on a single line, if any.
+fn line_coding_cookie(line: &[u8]) -> Option<String> {
+    let comment = line.trim_ascii_start().strip_prefix(b"#")?;
+    let comment = String::from_utf8_lossy(comment);
+
+    let (_, rest) = comment
+        .match_indices("coding")
+        .map(|(index, _)| comment.split_at(index + "coding".len()))
+        .find(|(_, rest)| rest.starts_with([':', '=']))?;
+
+    let name: String = rest[1..]
+        .trim_start_matches([' ', '\t'])
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .collect();
+    (!name.is_empty()).then_some(name)
+}
@@ -23,10 +23,26 @@ pub enum Error {
         module_path: Option<String>,
     },
 
+    /// The class given as a protocol doesn't inherit from `typing.Protocol`.
+    #[error("Class '{name}' is not a protocol (it doesn't inherit from typing.Protocol)")]
+    NotAProtocol { name: String },
+
+    /// The protocol has no members for classes to implement.
+    #[error("Protocol '{name}' has no members to implement")]
+    EmptyProtocol { name: String },
+
     /// IO error occurred while reading files.
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
+    /// Failed to decode a Python file with its declared (or the default) encoding.
+    #[error("Failed to decode {} as {encoding}", .file.display())]
+    DecodeError { file: PathBuf, encoding: String },
+
+    /// No `site-packages` directory was found in a Python environment.
+    #[error("No site-packages directory found in Python environment {}", .path.display())]
+    SitePackagesNotFound { path: PathBuf },
+
     /// Failed to parse a Python file.
     #[error("Failed to parse {}: {error}", .file.display())]
     ParseError { file: PathBuf, error: String },
@@ -3,8 +3,18 @@
 //! This module provides functionality to build and query a class inheritance graph.
 //! The graph maps parent classes to their direct and transitive children, enabling
 //! efficient subclass discovery.
+//!
+//! Besides inheritance, the graph records virtual subclass relationships created by
+//! registering a class with an abstract base class (e.g. `Base.register(Foo)`). These are
+//! kept as a separate kind of edge, which is only followed by the `*_with_virtual` queries.
+//!
+//! Base classes which aren't defined in the codebase, such as `django.db.models.Model` or
+//! `Exception`, are kept as "external" nodes, so that their subclasses can be found too.
+//! So are metaclasses which aren't defined in the codebase, such as `abc.ABCMeta`.
+//! External nodes have no parents of their own.
 
 use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 
 use crate::registry::{ClassId, Registry};
 
@@ -17,8 +27,18 @@ use crate::registry::{ClassId, Registry};
 pub struct InheritanceGraph {
     /// Maps parent classes to their direct children.
     pub children: HashMap<ClassId, HashSet<ClassId>>,
-    /// Maps child classes to their direct parents.
-    parents: HashMap<ClassId, HashSet<ClassId>>,
+    /// Maps child classes to their direct parents, in the order the bases are declared.
+    parents: HashMap<ClassId, Vec<ClassId>>,
+    /// Maps classes to the classes registered as their virtual subclasses.
+    virtual_children: HashMap<ClassId, HashSet<ClassId>>,
+    /// Maps classes to the classes they are registered with as a virtual subclass.
+    virtual_parents: HashMap<ClassId, HashSet<ClassId>>,
+    /// The external classes, i.e. base classes which aren't defined in the codebase.
+    external: HashSet<ClassId>,
+    /// The C3 linearizations computed so far (`None` for classes which have none).
+    ///
+    /// These are memoised since classes share most of their ancestors.
+    linearizations: Mutex<HashMap<ClassId, Option<Vec<ClassId>>>>,
 }
 
 impl InheritanceGraph {
@@ -39,18 +59,28 @@ impl InheritanceGraph {
     /// # Algorithm
     ///
     /// For each class in the registry:
-    /// 1. Resolve each of its base class names to a `ClassId`
+    /// 1. Resolve each of its base class names to a `ClassId`, or failing that to an
+    ///    external class (e.g. `django.db.models.Model`)
     /// 2. Add this class to the parent's children set
-    /// 3. Skip any base classes that cannot be resolved (e.g., external dependencies)
+    /// 3. Skip any base classes that cannot be resolved either way (e.g. names assigned
+    ///    the result of a call)
     pub fn build(registry: &Registry) -> Self {
         let mut children: HashMap<ClassId, HashSet<ClassId>> = HashMap::new();
-        let mut parents: HashMap<ClassId, HashSet<ClassId>> = HashMap::new();
+        let mut parents: HashMap<ClassId, Vec<ClassId>> = HashMap::new();
+        let mut external = HashSet::new();
 
         // Build parent → children and child → parents edges by examining each class's bases
         for (child_id, metadata) in &registry.classes {
-            for base_name in &metadata.bases {
-                // Resolve the base class reference in this class's module context
-                if let Some(parent_id) = registry.resolve_class(&child_id.module, base_name) {
+            for base in &metadata.bases {
+                // Resolve the base class reference in this class's scope
+                let parent_id = registry
+                    .resolve_base_class(child_id, &base.name)
+                    .or_else(|| {
+                        let external_id = registry.resolve_external_class(child_id, &base.name)?;
+                        external.insert(external_id.clone());
+                        Some(external_id)
+                    });
+                if let Some(parent_id) = parent_id {
                     // Add this class as a child of its parent
                     children
                         .entry(parent_id.clone())
@@ -58,15 +88,64 @@ impl InheritanceGraph {
                         .insert(child_id.clone());
 
                     // Add the parent as a parent of this class
-                    parents
-                        .entry(child_id.clone())
+                    let class_parents = parents.entry(child_id.clone()).or_default();
+                    if !class_parents.contains(&parent_id) {
+                        class_parents.push(parent_id);
+                    }
+                }
+            }
+        }
+
+        // Metaclasses which aren't defined in the codebase are external classes too
+        external.extend(
+            registry
+                .classes
+                .values()
+                .filter_map(|metadata| metadata.resolved_metaclass.as_ref())
+                .filter(|metaclass_id| !registry.classes.contains_key(metaclass_id))
+                .cloned(),
+        );
+
+        // Build the virtual subclass edges from registrations
+        let mut virtual_children: HashMap<ClassId, HashSet<ClassId>> = HashMap::new();
+        let mut virtual_parents: HashMap<ClassId, HashSet<ClassId>> = HashMap::new();
+        for (module, registrations) in &registry.registrations {
+            for registration in registrations {
+                if let Some((base_id, subclass_id)) =
+                    registry.resolve_registration(module, registration)
+                {
+                    virtual_children
+                        .entry(base_id.clone())
+                        .or_default()
+                        .insert(subclass_id.clone());
+                    virtual_parents
+                        .entry(subclass_id)
                         .or_default()
-                        .insert(parent_id);
+                        .insert(base_id);
                 }
             }
         }
 
-        Self { children, parents }
+        Self {
+            children,
+            parents,
+            virtual_children,
+            virtual_parents,
+            external,
+            linearizations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns whether a class is external, i.e. a base class which isn't defined in the
+    /// codebase (e.g. `django.db.models.Model`).
+    pub fn is_external(&self, id: &ClassId) -> bool {
+        self.external.contains(id)
+    }
+
+    /// Returns the external classes, i.e. the base classes which aren't defined in the
+    /// codebase.
+    pub fn external_classes(&self) -> impl Iterator<Item = &ClassId> {
+        self.external.iter()
     }
 
     /// Finds only the direct subclasses of a given class.
@@ -171,7 +250,8 @@ impl InheritanceGraph {
     ///
     /// # Returns
     ///
-    /// A vector containing only the direct parent classes.
+    /// A vector containing only the direct parent classes, in the order the base
+    /// classes are declared.
     ///
     /// # Examples
     ///
@@ -185,10 +265,7 @@ impl InheritanceGraph {
     /// find_direct_parent_classes(Mammal) → [Animal]
     /// ```
     pub fn find_direct_parent_classes(&self, root: &ClassId) -> Vec<ClassId> {
-        self.parents
-            .get(root)
-            .map(|parents| parents.iter().cloned().collect())
-            .unwrap_or_default()
+        self.parents.get(root).cloned().unwrap_or_default()
     }
 
     /// Finds all transitive parent classes of a given class.
@@ -247,4 +324,176 @@ impl InheritanceGraph {
 
         result
     }
+
+    /// Computes the method resolution order (MRO) of a given class.
+    ///
+    /// Uses the C3 linearization Python itself uses, over the parent classes in the
+    /// graph. External classes (such as `object` or classes from third-party packages)
+    /// are included, but without their own parent classes. If the hierarchy has no
+    /// consistent linearization (which Python would reject with a `TypeError`), or
+    /// contains a cycle, the class followed by its parents in BFS order is returned
+    /// instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The class to compute the MRO of
+    ///
+    /// # Returns
+    ///
+    /// A vector starting with the class itself, followed by its parent classes in the
+    /// order attributes are looked up in them.
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// Given:
+    ///   class Base: pass
+    ///   class Left(Base): pass
+    ///   class Right(Base): pass
+    ///   class Child(Left, Right): pass
+    ///
+    /// mro(Child) → [Child, Left, Right, Base]
+    /// ```
+    pub fn mro(&self, root: &ClassId) -> Vec<ClassId> {
+        self.linearize(root, &mut Vec::new()).unwrap_or_else(|| {
+            let mut result = vec![root.clone()];
+            result.extend(self.find_all_parent_classes(root));
+            result
+        })
+    }
+
+    /// Computes the C3 linearization of a class, or `None` if there is none.
+    ///
+    /// `stack` holds the classes currently being linearized, to detect cycles.
+    fn linearize(&self, class: &ClassId, stack: &mut Vec<ClassId>) -> Option<Vec<ClassId>> {
+        if let Some(linearization) = self.memoised_linearizations().get(class) {
+            return linearization.clone();
+        }
+        if stack.contains(class) {
+            return None;
+        }
+
+        stack.push(class.clone());
+        let linearization = self.merge_linearizations(class, stack);
+        stack.pop();
+
+        // Failures are memoised too: a class whose parents fail to linearize (or which
+        // is part of a cycle) fails whichever class the linearization started from
+        self.memoised_linearizations()
+            .insert(class.clone(), linearization.clone());
+        linearization
+    }
+
+    /// Returns the memoised linearizations.
+    fn memoised_linearizations(&self) -> MutexGuard<'_, HashMap<ClassId, Option<Vec<ClassId>>>> {
+        self.linearizations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Merges the linearizations of the parents of a class, as C3 linearization does.
+    fn merge_linearizations(
+        &self,
+        class: &ClassId,
+        stack: &mut Vec<ClassId>,
+    ) -> Option<Vec<ClassId>> {
+        // The linearizations of the parents, followed by the parents themselves
+        let parents = self.find_direct_parent_classes(class);
+        let mut sequences = Vec::new();
+        for parent in &parents {
+            sequences.push(self.linearize(parent, stack)?);
+        }
+        sequences.push(parents);
+
+        let mut result = vec![class.clone()];
+        loop {
+            sequences.retain(|sequence| !sequence.is_empty());
+            if sequences.is_empty() {
+                return Some(result);
+            }
+
+            // The next class is the first head which isn't in the tail of any sequence
+            let next = sequences
+                .iter()
+                .map(|sequence| &sequence[0])
+                .find(|head| {
+                    !sequences
+                        .iter()
+                        .any(|sequence| sequence[1..].contains(head))
+                })?
+                .clone();
+            for sequence in &mut sequences {
+                if sequence[0] == next {
+                    sequence.remove(0);
+                }
+            }
+            result.push(next);
+        }
+    }
+
+    /// Finds the direct subclasses of a given class, including virtual subclasses
+    /// registered with it.
+    ///
+    /// See [`InheritanceGraph::find_direct_subclasses`].
+    pub fn find_direct_subclasses_with_virtual(&self, root: &ClassId) -> Vec<ClassId> {
+        let mut result = self.find_direct_subclasses(root);
+        if let Some(virtual_children) = self.virtual_children.get(root) {
+            for child in virtual_children {
+                if !result.contains(child) {
+                    result.push(child.clone());
+                }
+            }
+        }
+        result
+    }
+
+    /// Finds all transitive subclasses of a given class, including virtual subclasses.
+    ///
+    /// Both inheritance and registration edges are followed, so the subclasses of a
+    /// registered class are virtual subclasses too (as `isinstance` reports).
+    ///
+    /// See [`InheritanceGraph::find_all_subclasses`].
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// Given:
+    ///   class Animal(abc.ABC): pass
+    ///   class Robot: pass
+    ///   class RoboDog(Robot): pass
+    ///   Animal.register(Robot)
+    ///
+    /// find_all_subclasses_with_virtual(Animal) → [Robot, RoboDog]
+    /// ```
+    pub fn find_all_subclasses_with_virtual(&self, root: &ClassId) -> Vec<ClassId> {
+        use std::collections::VecDeque;
+
+        let mut result = Vec::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        queue.push_back(root.clone());
+        visited.insert(root.clone());
+
+        while let Some(current) = queue.pop_front() {
+            let children = self.children.get(&current).into_iter().flatten();
+            let virtual_children = self.virtual_children.get(&current).into_iter().flatten();
+            for child in children.chain(virtual_children) {
+                if visited.insert(child.clone()) {
+                    result.push(child.clone());
+                    queue.push_back(child.clone());
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Finds the classes a given class is registered with as a virtual subclass.
+    pub fn find_virtual_parent_classes(&self, root: &ClassId) -> Vec<ClassId> {
+        self.virtual_parents
+            .get(root)
+            .map(|parents| parents.iter().cloned().collect())
+            .unwrap_or_default()
+    }
 }
@@ -22,19 +22,46 @@
 //! ```
 
 pub mod cache;
+pub mod classify;
 pub mod discovery;
+pub mod encoding;
 pub mod error;
 pub mod graph;
+pub mod notebook;
 pub mod parser;
 pub mod registry;
 
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
 
+pub use classify::{AbstractMethod, ClassTag};
+pub use discovery::StubPolicy;
 pub use error::{Error, Result};
 use graph::InheritanceGraph;
+pub use parser::{
+    BaseClass, ClassDecorator, ClassMember, LiteralValue, Location, MemberKind, ParseOptions,
+};
 
 use crate::registry::Registry;
 
+/// Special attributes which aren't considered members of a protocol.
+const PROTOCOL_EXCLUDED_MEMBERS: &[&str] = &[
+    "__annotations__",
+    "__class_getitem__",
+    "__dict__",
+    "__doc__",
+    "__init__",
+    "__match_args__",
+    "__module__",
+    "__new__",
+    "__slots__",
+    "__subclasshook__",
+    "__weakref__",
+];
+
 /// A reference to a Python class.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ClassReference {
@@ -42,8 +69,67 @@ pub struct ClassReference {
     pub class_name: String,
     /// The module path where the class is defined (e.g., "foo.bar")
     pub module_path: String,
-    /// The file path where the class is defined
+    /// The file path where the class is defined (a `.pyi` file if it comes from a stub)
     pub file_path: PathBuf,
+    /// The location of the class definition within the file
+    pub location: Location,
+    /// The guard(s) the class is defined under, if it is defined conditionally
+    /// (e.g. "if TYPE_CHECKING" or "try, except ImportError")
+    pub guard: Option<String>,
+    /// Whether the class is created dynamically, e.g. `Foo = type("Foo", (Base,), {})`
+    pub is_dynamic: bool,
+    /// Whether the class is defined in a file that was only partially parsed, because of
+    /// syntax errors
+    pub is_partial: bool,
+    /// Whether the class is only a virtual subclass of the searched-for class, i.e. it
+    /// (or one of its parents) is registered with it (e.g. `Base.register(Foo)`) rather
+    /// than inheriting from it
+    pub is_virtual: bool,
+    /// The metaclass declared by the class as written (e.g. "Meta"), if any
+    pub metaclass: Option<String>,
+    /// The base classes of the class, including any type arguments
+    /// (e.g. `Repository[User]`)
+    ///
+    /// Base class names and type arguments are given as qualified names
+    /// (e.g. "models.User") if they resolve to a class in the codebase, and as written
+    /// otherwise.
+    pub bases: Vec<BaseClass>,
+    /// How the class relates to the member named in [`SearchOptions::member`], if a
+    /// member was given
+    pub member_status: Option<MemberStatus>,
+    /// The abstract methods the class inherits without implementing, as found by
+    /// [`SubclassFinder::find_unimplemented_abstract_methods`] (empty for other queries)
+    pub unimplemented_abstract_methods: Vec<AbstractMethod>,
+    /// The kinds of class the class is (e.g. abstract, enum or dataclass), sorted
+    ///
+    /// Only filled in if requested (see [`SearchOptions::include_tags`] and
+    /// [`SubclassFinder::classify_references`]), since classifying a class walks its
+    /// ancestry.
+    pub tags: Vec<ClassTag>,
+    /// The decorators applied to the class, with their literal arguments
+    ///
+    /// Decorator names are qualified by the import they refer to
+    /// (e.g. "plugins.register_backend" after `from plugins import register_backend`).
+    pub decorators: Vec<ClassDecorator>,
+    /// The values of the attributes named in [`SearchOptions::attributes`], in the same
+    /// order
+    pub attributes: Vec<AttributeValue>,
+    /// The fields of the class, including inherited fields, if
+    /// [`SearchOptions::include_fields`] is set (see [`SubclassFinder::find_fields`])
+    pub fields: Vec<Field>,
+    /// Whether the class is defined in a library root (see [`FinderOptions::library_roots`])
+    pub is_library: bool,
+    /// Whether the class is external, i.e. a base class from the standard library or a
+    /// third-party package which isn't defined in the codebase (e.g.
+    /// `django.db.models.Model`)
+    ///
+    /// External classes have an empty file path and a zero location, and no bases.
+    pub is_external: bool,
+    /// The members of the class if it is an enum, in definition order (empty otherwise)
+    ///
+    /// Only filled in by the enum queries, or if requested (see
+    /// [`SearchOptions::include_enum_members`] and [`SubclassFinder::list_enum_members`]).
+    pub enum_members: Vec<EnumMember>,
 }
 
 impl ClassReference {
@@ -51,6 +137,100 @@ impl ClassReference {
     pub fn qualified_name(&self) -> String {
         format!("{}.{}", self.module_path, self.class_name)
     }
+
+    /// Returns whether this is a local class, i.e. a class defined inside a function
+    /// or method (e.g. "make_handler.<locals>.Handler").
+    pub fn is_local(&self) -> bool {
+        self.class_name.contains(".<locals>.")
+    }
+
+    /// Returns whether the class is defined in a stub file (`.pyi`).
+    pub fn is_stub(&self) -> bool {
+        parser::is_stub_file(&self.file_path)
+    }
+
+    /// Returns the first decorator of the class with the given name, matched as described
+    /// in [`SearchOptions::decorated_with`].
+    pub fn find_decorator(&self, name: &str) -> Option<&ClassDecorator> {
+        self.decorators.iter().find(|decorator| {
+            decorator.name == name
+                || decorator
+                    .name
+                    .strip_suffix(name)
+                    .is_some_and(|prefix| prefix.ends_with('.'))
+        })
+    }
+}
+
+/// The value of a class attribute, as defined by a class or inherited from a parent class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttributeValue {
+    /// The name of the attribute
+    pub name: String,
+    /// The literal value of the attribute, or `None` if it isn't defined, or is defined
+    /// by a method or assigned something other than a literal
+    pub value: Option<LiteralValue>,
+    /// The qualified name of the class defining the attribute (the nearest in the MRO),
+    /// or `None` if no class in the codebase defines it
+    pub defined_in: Option<String>,
+}
+
+/// A field of a class, i.e. an annotated class-level attribute (e.g. `x: int = 0`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The name of the field
+    pub name: String,
+    /// The type annotation of the field, as written (e.g. "list[str]")
+    pub annotation: String,
+    /// The default value of the field, if it is a literal
+    pub default: Option<LiteralValue>,
+    /// The qualified name of the class defining the field (e.g. "models.Base")
+    pub defined_in: String,
+}
+
+/// A member of an enum, e.g. `RED = 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumMember {
+    /// The name of the member
+    pub name: String,
+    /// The value of the member, if it is a literal (e.g. not `auto()`)
+    pub value: Option<LiteralValue>,
+}
+
+/// A module provided by more than one source or library root (e.g. `pkg/models.py` in
+/// two services of a monorepo).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleConflict {
+    /// The module path (e.g. "pkg.models")
+    pub module_path: String,
+    /// The file the module is indexed from
+    pub indexed_file: PathBuf,
+    /// The file providing the same module from another root, which isn't indexed
+    pub ignored_file: PathBuf,
+}
+
+/// How a class relates to a member (method or attribute) name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemberStatus {
+    /// The class defines the member, and none of its parent classes do
+    Defines,
+    /// The class defines the member, overriding a definition in a parent class
+    Overrides,
+    /// The class doesn't define the member, but inherits it from a parent class
+    Inherits,
+    /// Neither the class nor any of its parent classes define the member
+    Missing,
+}
+
+impl fmt::Display for MemberStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            MemberStatus::Defines => "defines",
+            MemberStatus::Overrides => "overrides",
+            MemberStatus::Inherits => "inherits",
+            MemberStatus::Missing => "missing",
+        })
+    }
 }
 
 /// Mode for searching the inheritance graph.
@@ -62,6 +242,128 @@ pub enum SearchMode {
     All,
 }
 
+/// Options for building a [`SubclassFinder`].
+#[derive(Debug, Clone)]
+pub struct FinderOptions {
+    /// Directories to exclude from the search
+    pub exclude_dirs: Vec<PathBuf>,
+    /// Whether to use the cache for faster repeated runs
+    pub use_cache: bool,
+    /// Options controlling how Python files are parsed
+    pub parse_options: ParseOptions,
+    /// Which file to index for modules with both a source file and a stub file
+    pub stub_policy: StubPolicy,
+    /// Source roots within the project (e.g. `src`, or `services/api/src` in a monorepo),
+    /// relative to the root directory or absolute
+    ///
+    /// Each file's module path is relative to the deepest source root containing it (e.g.
+    /// `pkg.models` for `src/pkg/models.py`), and files outside all source roots have
+    /// module paths relative to the root directory. Source roots outside the root
+    /// directory are searched too. Modules provided by more than one root are reported
+    /// by [`SubclassFinder::module_conflicts`].
+    pub source_roots: Vec<PathBuf>,
+    /// Library roots to index besides the project root, e.g. the `site-packages`
+    /// directories of a virtualenv (see [`discovery::find_site_packages`])
+    ///
+    /// Module paths are relative to each library root (e.g. `django.views.generic`).
+    /// Library roots are read-only: their ignore files are not taken into account, and
+    /// their parse results are cached in the project root.
+    pub library_roots: Vec<PathBuf>,
+    /// Whether to report classes defined in library roots
+    ///
+    /// Library classes are always traversed, so that e.g. a project class inheriting
+    /// from `django.views.View` through Django REST framework classes is found, but
+    /// by default only project classes are reported. This doesn't apply to the parent
+    /// classes found by [`SubclassFinder::find_parent_classes`].
+    pub include_library: bool,
+}
+
+impl Default for FinderOptions {
+    fn default() -> Self {
+        Self {
+            exclude_dirs: Vec::new(),
+            use_cache: true,
+            parse_options: ParseOptions::default(),
+            stub_policy: StubPolicy::default(),
+            source_roots: Vec::new(),
+            library_roots: Vec::new(),
+            include_library: false,
+        }
+    }
+}
+
+/// Options for filtering the results of a subclass search.
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// Whether to include local classes (classes defined inside functions or methods)
+    pub include_local: bool,
+    /// Only include classes specialising the searched-for class with this type argument
+    /// (e.g. "User" matches `class UserRepo(Repository[User])` when searching for
+    /// `Repository`), either directly or through a parent class
+    ///
+    /// Matches type arguments equal to the name, or whose last component is the name
+    /// (e.g. "models.User").
+    pub type_arg: Option<String>,
+    /// Whether to include virtual subclasses, i.e. classes registered with the
+    /// searched-for class (e.g. `Base.register(Foo)`) and their subclasses
+    pub include_virtual: bool,
+    /// The name of a member (method or attribute) to report the status of for each class,
+    /// in [`ClassReference::member_status`]
+    ///
+    /// Only members defined in class bodies in the codebase are taken into account.
+    pub member: Option<String>,
+    /// Only include classes with one of these statuses for [`SearchOptions::member`]
+    ///
+    /// All classes are included if this is empty. Ignored if no member is given.
+    pub member_statuses: Vec<MemberStatus>,
+    /// Only include classes with at least one of these tags (e.g. [`ClassTag::Concrete`])
+    ///
+    /// All classes are included if this is empty.
+    pub only: Vec<ClassTag>,
+    /// Whether to classify each class, in [`ClassReference::tags`]
+    ///
+    /// Classes are always classified if [`SearchOptions::only`] isn't empty.
+    pub include_tags: bool,
+    /// Whether to list the members of each enum, in [`ClassReference::enum_members`]
+    pub include_enum_members: bool,
+    /// Only include classes decorated with this decorator (e.g. "register_backend")
+    ///
+    /// Matches decorators whose name, as written or qualified by its import, is equal to
+    /// the given name or ends with `.` followed by it. Only decorators applied to the
+    /// class itself count, not those of its parent classes.
+    pub decorated_with: Option<String>,
+    /// Only include classes not decorated with this decorator, matched like
+    /// [`SearchOptions::decorated_with`]
+    pub not_decorated_with: Option<String>,
+    /// The names of class attributes to report the values of for each class, in
+    /// [`ClassReference::attributes`]
+    ///
+    /// Attributes a class doesn't define itself are looked up in its parent classes, in
+    /// MRO order.
+    pub attributes: Vec<String>,
+    /// Whether to list the fields of each class, in [`ClassReference::fields`]
+    pub include_fields: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            include_local: true,
+            type_arg: None,
+            include_virtual: false,
+            member: None,
+            member_statuses: Vec::new(),
+            only: Vec::new(),
+            include_tags: false,
+            include_enum_members: false,
+            decorated_with: None,
+            not_decorated_with: None,
+            attributes: Vec::new(),
+            include_fields: false,
+        }
+    }
+}
+
 /// The main entry point for finding Python subclasses.
 ///
 /// # Examples
@@ -85,6 +387,9 @@ pub enum SearchMode {
 pub struct SubclassFinder {
     registry: Registry,
     graph: InheritanceGraph,
+    library_roots: Vec<PathBuf>,
+    include_library: bool,
+    module_conflicts: Vec<ModuleConflict>,
 }
 
 impl SubclassFinder {
@@ -123,37 +428,187 @@ impl SubclassFinder {
         exclude_dirs: Vec<PathBuf>,
         use_cache: bool,
     ) -> Result<Self> {
+        Self::from_options(
+            root_dir,
+            FinderOptions {
+                exclude_dirs,
+                use_cache,
+                ..FinderOptions::default()
+            },
+        )
+    }
+
+    /// Creates a new SubclassFinder from a full set of options.
+    ///
+    /// # Arguments
+    ///
+    /// * `root_dir` - The root directory to search for Python files
+    /// * `options` - Options controlling discovery and parsing
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The directory cannot be read
+    /// - Any Python files cannot be parsed
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::{FinderOptions, SubclassFinder};
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// // Teach the parser that `compat.with_base(Base)` forwards `Base` as a base class
+    /// let mut options = FinderOptions::default();
+    /// options
+    ///     .parse_options
+    ///     .base_forwarding_calls
+    ///     .push("compat.with_base".to_string());
+    /// let finder = SubclassFinder::from_options(PathBuf::from("."), options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_options(root_dir: PathBuf, options: FinderOptions) -> Result<Self> {
         let root_dir = root_dir.canonicalize()?;
+        let source_roots = options
+            .source_roots
+            .iter()
+            .map(|source_root| root_dir.join(source_root).canonicalize())
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let library_roots = options
+            .library_roots
+            .iter()
+            .map(|library_root| library_root.canonicalize())
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        // The root directory is the source root of files outside the given source roots
+        let mut project_roots = source_roots.clone();
+        if !project_roots.contains(&root_dir) {
+            project_roots.push(root_dir.clone());
+        }
 
-        // Discover all Python files
-        let python_files =
-            discovery::discover_python_files_with_exclusions(&root_dir, &exclude_dirs)?;
+        // Discover all Python files in the root directory and the source roots outside it,
+        // leaving library roots within them (e.g. a virtualenv which isn't gitignored) to
+        // be indexed as libraries
+        let mut exclude_dirs = options.exclude_dirs.clone();
+        exclude_dirs.extend(library_roots.iter().cloned());
+        let mut python_files = Vec::new();
+        for search_root in std::iter::once(&root_dir).chain(
+            source_roots
+                .iter()
+                .filter(|source_root| !source_root.starts_with(&root_dir)),
+        ) {
+            python_files.extend(discovery::discover_python_files_with_exclusions(
+                search_root,
+                &exclude_dirs,
+            )?);
+        }
+        let mut seen = HashSet::new();
+        python_files.retain(|file_path| seen.insert(file_path.clone()));
+        let python_files = discovery::apply_stub_policy(python_files, options.stub_policy);
+
+        // Group the files by the root their module paths are relative to
+        let mut roots: Vec<(PathBuf, Vec<PathBuf>)> = project_roots
+            .iter()
+            .map(|project_root| (project_root.clone(), Vec::new()))
+            .collect();
+        for file_path in python_files {
+            if let Some(index) = discovery::owning_root(&file_path, &project_roots)
+                .and_then(|owning_root| project_roots.iter().position(|root| root == owning_root))
+            {
+                roots[index].1.push(file_path);
+            }
+        }
+        for library_root in &library_roots {
+            let library_files = discovery::discover_library_files(library_root)?;
+            let library_files = discovery::apply_stub_policy(library_files, options.stub_policy);
+            log::debug!(
+                "Found {} Python files in library root {}",
+                library_files.len(),
+                library_root.display()
+            );
+            roots.push((library_root.clone(), library_files));
+        }
 
         // Parse files in parallel (with optional caching)
-        let parse_results = if use_cache {
-            cache::parse_with_cache(&root_dir, &python_files)?
+        let parse_results = if options.use_cache {
+            cache::parse_roots_with_cache(&root_dir, &roots, &options.parse_options)?
         } else {
-            parser::parse_files(&root_dir, &python_files)?
+            roots
+                .iter()
+                .map(|(root, python_files)| {
+                    parser::parse_files_with_options(root, python_files, &options.parse_options)
+                })
+                .collect::<Result<Vec<_>>>()?
         };
 
-        // Log any parse errors and collect successful parses
-        let parsed_files: Vec<_> = parse_results
-            .into_iter()
-            .filter_map(|result| match result {
-                Ok(parsed) => Some(parsed),
-                Err(e) => {
-                    log::warn!("{e}");
-                    None
+        // Log any parse errors and collect successful parses. Each module is indexed from
+        // the first root providing it (source roots in the order given, then the root
+        // directory, then library roots), and any other root providing it is reported.
+        let mut module_files: HashMap<String, (usize, PathBuf)> = HashMap::new();
+        let mut module_conflicts = Vec::new();
+        let mut parsed_files = Vec::new();
+        for (root_index, results) in parse_results.into_iter().enumerate() {
+            for result in results {
+                let parsed = match result {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        log::warn!("{e}");
+                        continue;
+                    }
+                };
+                let (module_root_index, indexed_file) = module_files
+                    .entry(parsed.module_path.clone())
+                    .or_insert_with(|| (root_index, parsed.file_path.clone()));
+                if *module_root_index != root_index {
+                    log::debug!(
+                        "Module '{}' is provided by both {} and {}, ignoring the latter",
+                        parsed.module_path,
+                        indexed_file.display(),
+                        parsed.file_path.display()
+                    );
+                    module_conflicts.push(ModuleConflict {
+                        module_path: parsed.module_path,
+                        indexed_file: indexed_file.clone(),
+                        ignored_file: parsed.file_path,
+                    });
+                    continue;
                 }
-            })
-            .collect();
+                parsed_files.push(parsed);
+            }
+        }
+        module_conflicts.sort_by(|a, b| {
+            a.module_path
+                .cmp(&b.module_path)
+                .then(a.ignored_file.cmp(&b.ignored_file))
+        });
+
+        // Index project files before library files, and stub files first, so that classes
+        // defined in both a stub and its source are reported from the stub
+        let is_library_file = |file_path: &Path| {
+            library_roots
+                .iter()
+                .any(|library_root| file_path.starts_with(library_root))
+        };
+        parsed_files.sort_by_key(|parsed| {
+            (
+                is_library_file(&parsed.file_path),
+                !parser::is_stub_file(&parsed.file_path),
+            )
+        });
 
         let registry = Registry::build(&parsed_files)?;
 
         // Build the inheritance graph
         let graph = InheritanceGraph::build(&registry);
 
-        Ok(Self { registry, graph })
+        Ok(Self {
+            registry,
+            graph,
+            library_roots,
+            include_library: options.include_library,
+            module_conflicts,
+        })
     }
 
     /// Finds subclasses of a given class with a specified mode.
@@ -198,43 +653,189 @@ impl SubclassFinder {
         class_name: &str,
         module_path: Option<&str>,
         mode: SearchMode,
+    ) -> Result<Vec<ClassReference>> {
+        self.find_subclasses_with_options(class_name, module_path, mode, &SearchOptions::default())
+    }
+
+    /// Finds subclasses of a given class with a specified mode and search options.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_name` - The simple name of the class to find subclasses for
+    /// * `module_path` - Optional module path to disambiguate the class if the name
+    ///   appears multiple times in the codebase
+    /// * `mode` - Whether to find only direct subclasses or all transitive subclasses
+    /// * `options` - Options for filtering the results
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The class is not found
+    /// - The class name is ambiguous and no module path is provided
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::{SearchMode, SearchOptions, SubclassFinder};
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// // Find all subclasses, excluding classes defined inside functions
+    /// let options = SearchOptions {
+    ///     include_local: false,
+    ///     ..SearchOptions::default()
+    /// };
+    /// let subclasses =
+    ///     finder.find_subclasses_with_options("Animal", None, SearchMode::All, &options)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_subclasses_with_options(
+        &self,
+        class_name: &str,
+        module_path: Option<&str>,
+        mode: SearchMode,
+        options: &SearchOptions,
     ) -> Result<Vec<ClassReference>> {
         // Find the target class
         let target_id = self.resolve_target_class(class_name, module_path)?;
 
         // Find subclasses using the graph based on the mode
-        let subclass_ids = match mode {
+        let inheriting_ids = match mode {
             SearchMode::Direct => self.graph.find_direct_subclasses(&target_id),
             SearchMode::All => self.graph.find_all_subclasses(&target_id),
         };
+        let mut subclass_ids = match (mode, options.include_virtual) {
+            (_, false) => inheriting_ids.clone(),
+            (SearchMode::Direct, true) => {
+                self.graph.find_direct_subclasses_with_virtual(&target_id)
+            }
+            (SearchMode::All, true) => self.graph.find_all_subclasses_with_virtual(&target_id),
+        };
 
-        // Convert to ClassReference
-        let mut results: Vec<ClassReference> = subclass_ids
-            .into_iter()
-            .filter_map(|id| {
-                self.registry
-                    .modules
-                    .get(&id.module)
-                    .map(|metadata| ClassReference {
-                        class_name: id.name.clone(),
-                        module_path: id.module.clone(),
-                        file_path: metadata.file_path.clone(),
+        if let Some(type_arg) = &options.type_arg {
+            subclass_ids.retain(|id| {
+                self.find_type_args(id, &target_id, &mut HashSet::new())
+                    .is_some_and(|args| {
+                        args.iter().any(|arg| {
+                            arg == type_arg || arg.rsplit('.').next() == Some(type_arg.as_str())
+                        })
                     })
-            })
-            .collect();
+            });
+        }
 
-        // Sort by module path for consistent output
-        results.sort_by(|a, b| {
-            a.module_path
-                .cmp(&b.module_path)
-                .then(a.class_name.cmp(&b.class_name))
-        });
+        let inheriting_ids: HashSet<registry::ClassId> = inheriting_ids.into_iter().collect();
+        let mut results = self.to_reported_references(&subclass_ids);
+        for class_ref in &mut results {
+            class_ref.is_virtual = !inheriting_ids.contains(&registry::ClassId {
+                module: class_ref.module_path.clone(),
+                name: class_ref.class_name.clone(),
+            });
+        }
+        if !options.include_local {
+            results.retain(|class_ref| !class_ref.is_local());
+        }
+
+        if let Some(decorator) = &options.decorated_with {
+            results.retain(|class_ref| class_ref.find_decorator(decorator).is_some());
+        }
+        if let Some(decorator) = &options.not_decorated_with {
+            results.retain(|class_ref| class_ref.find_decorator(decorator).is_none());
+        }
+
+        if options.include_tags || !options.only.is_empty() {
+            self.classify_references(&mut results);
+        }
+        if !options.only.is_empty() {
+            results.retain(|class_ref| class_ref.tags.iter().any(|tag| options.only.contains(tag)));
+        }
+
+        if !options.attributes.is_empty() {
+            for class_ref in &mut results {
+                let id = registry::ClassId {
+                    module: class_ref.module_path.clone(),
+                    name: class_ref.class_name.clone(),
+                };
+                class_ref.attributes = options
+                    .attributes
+                    .iter()
+                    .map(|name| self.attribute_value(&id, name))
+                    .collect();
+            }
+        }
+
+        if options.include_enum_members {
+            self.list_enum_members(&mut results);
+        }
+
+        if options.include_fields {
+            for class_ref in &mut results {
+                class_ref.fields = self.fields(&registry::ClassId {
+                    module: class_ref.module_path.clone(),
+                    name: class_ref.class_name.clone(),
+                });
+            }
+        }
+
+        if let Some(member) = &options.member {
+            for class_ref in &mut results {
+                let id = registry::ClassId {
+                    module: class_ref.module_path.clone(),
+                    name: class_ref.class_name.clone(),
+                };
+                class_ref.member_status = Some(self.member_status(&id, member));
+            }
+            if !options.member_statuses.is_empty() {
+                results.retain(|class_ref| {
+                    class_ref
+                        .member_status
+                        .is_some_and(|status| options.member_statuses.contains(&status))
+                });
+            }
+        }
 
         Ok(results)
     }
 
+    /// Classifies classes found by a query, filling in their [`ClassReference::tags`].
+    ///
+    /// Queries leave the tags empty, unless [`SearchOptions::include_tags`] is set.
+    pub fn classify_references(&self, class_refs: &mut [ClassReference]) {
+        for class_ref in class_refs {
+            class_ref.tags = classify::classify(
+                &self.registry,
+                &self.graph,
+                &registry::ClassId {
+                    module: class_ref.module_path.clone(),
+                    name: class_ref.class_name.clone(),
+                },
+            );
+        }
+    }
+
+    /// Lists the members of the enums among classes found by a query, in their
+    /// [`ClassReference::enum_members`].
+    ///
+    /// Queries other than [`SubclassFinder::find_enums`] and
+    /// [`SubclassFinder::find_all_enums`] leave the members empty, unless
+    /// [`SearchOptions::include_enum_members`] is set.
+    pub fn list_enum_members(&self, class_refs: &mut [ClassReference]) {
+        for class_ref in class_refs {
+            class_ref.enum_members = self.enum_members(&registry::ClassId {
+                module: class_ref.module_path.clone(),
+                name: class_ref.class_name.clone(),
+            });
+        }
+    }
+
     /// Finds parent classes of a given class with a specified mode.
     ///
+    /// Only classes defined in the codebase are returned. Use
+    /// [`SubclassFinder::find_parent_classes_with_external`] to include external classes
+    /// (e.g. `builtins.Exception` or `django.db.models.Model`).
+    ///
     /// # Arguments
     ///
     /// * `class_name` - The simple name of the class to find parent classes for
@@ -275,6 +876,44 @@ impl SubclassFinder {
         class_name: &str,
         module_path: Option<&str>,
         mode: SearchMode,
+    ) -> Result<Vec<ClassReference>> {
+        let mut parents = self.find_parent_classes_with_external(class_name, module_path, mode)?;
+        parents.retain(|parent| !parent.is_external);
+        Ok(parents)
+    }
+
+    /// Finds parent classes of a given class with a specified mode, including external
+    /// classes.
+    ///
+    /// External classes are base classes which aren't defined in the codebase, such as
+    /// `builtins.object`, `builtins.Exception` or `django.db.models.Model`. They are
+    /// marked with [`ClassReference::is_external`], and their own parent classes are
+    /// unknown.
+    ///
+    /// See [`SubclassFinder::find_parent_classes`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::{SubclassFinder, SearchMode};
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// for parent in finder.find_parent_classes_with_external("Article", None, SearchMode::Direct)? {
+    ///     if parent.is_external {
+    ///         println!("{}", parent.qualified_name());
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_parent_classes_with_external(
+        &self,
+        class_name: &str,
+        module_path: Option<&str>,
+        mode: SearchMode,
     ) -> Result<Vec<ClassReference>> {
         // Find the target class
         let class_id = self.resolve_target_class(class_name, module_path)?;
@@ -285,42 +924,460 @@ impl SubclassFinder {
             SearchMode::All => self.graph.find_all_parent_classes(&class_id),
         };
 
-        // Convert to ClassReference
-        let mut results: Vec<ClassReference> = parent_ids
+        Ok(self.to_sorted_references(&parent_ids))
+    }
+
+    /// Finds the classes a given class is registered with as a virtual subclass
+    /// (e.g. `Base` for `Base.register(Foo)`).
+    ///
+    /// # Arguments
+    ///
+    /// * `class_name` - The simple name of the registered class
+    /// * `module_path` - Optional module path to disambiguate the class if the name
+    ///   appears multiple times in the codebase
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of the classes the class is registered with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The class is not found
+    /// - The class name is ambiguous and no module path is provided
+    pub fn find_virtual_parent_classes(
+        &self,
+        class_name: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<ClassReference>> {
+        let class_id = self.resolve_target_class(class_name, module_path)?;
+        let parent_ids = self.graph.find_virtual_parent_classes(&class_id);
+        Ok(self.to_sorted_references(&parent_ids))
+    }
+
+    /// Finds all classes whose metaclass is a given class or one of its subclasses.
+    ///
+    /// This includes classes declaring the metaclass (via `metaclass=Meta`,
+    /// `six.with_metaclass(Meta, ...)` or `@six.add_metaclass(Meta)`), as well as
+    /// their subclasses, which inherit the metaclass.
+    ///
+    /// # Arguments
+    ///
+    /// * `metaclass_name` - The simple name of the metaclass
+    /// * `module_path` - Optional module path to disambiguate the metaclass if the name
+    ///   appears multiple times in the codebase
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of the classes using the metaclass.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The metaclass is not found
+    /// - The metaclass name is ambiguous and no module path is provided
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::SubclassFinder;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// // Find all classes (transitively) using `RegistryMeta`
+    /// let classes = finder.find_classes_with_metaclass("RegistryMeta", None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_classes_with_metaclass(
+        &self,
+        metaclass_name: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<ClassReference>> {
+        let metaclass_id = self.resolve_target_class(metaclass_name, module_path)?;
+
+        // The metaclass itself, or any metaclass derived from it
+        let mut metaclass_ids: HashSet<registry::ClassId> = self
+            .graph
+            .find_all_subclasses(&metaclass_id)
             .into_iter()
-            .filter_map(|id| {
-                self.registry
-                    .modules
-                    .get(&id.module)
-                    .map(|metadata| ClassReference {
-                        class_name: id.name.clone(),
-                        module_path: id.module.clone(),
-                        file_path: metadata.file_path.clone(),
-                    })
+            .collect();
+        metaclass_ids.insert(metaclass_id);
+
+        // Classes declaring one of these metaclasses, plus their subclasses
+        let mut class_ids = HashSet::new();
+        for (class_id, metadata) in &self.registry.classes {
+            if metadata
+                .resolved_metaclass
+                .as_ref()
+                .is_some_and(|metaclass| metaclass_ids.contains(metaclass))
+            {
+                class_ids.extend(self.graph.find_all_subclasses(class_id));
+                class_ids.insert(class_id.clone());
+            }
+        }
+
+        let class_ids: Vec<registry::ClassId> = class_ids.into_iter().collect();
+        Ok(self.to_reported_references(&class_ids))
+    }
+
+    /// Finds the classes which structurally satisfy a protocol, i.e. which define (or
+    /// inherit) every member of it.
+    ///
+    /// The members of the protocol are the methods and attributes defined in its body
+    /// and in the bodies of its parent protocols, apart from special attributes such as
+    /// `__init__` and `__slots__`. A class satisfies the protocol if each of these names
+    /// is defined in the class body or in the body of one of its parent classes in the
+    /// codebase. Only names are compared, not signatures or types, and attributes only
+    /// assigned on instances (e.g. `self.name = name`) are not taken into account.
+    ///
+    /// Protocol classes themselves (classes inheriting from `typing.Protocol`) are not
+    /// included.
+    ///
+    /// # Arguments
+    ///
+    /// * `protocol_name` - The simple name of the protocol
+    /// * `module_path` - Optional module path to disambiguate the protocol if the name
+    ///   appears multiple times in the codebase
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of the classes satisfying the protocol.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The protocol is not found
+    /// - The protocol name is ambiguous and no module path is provided
+    /// - The class doesn't inherit from `typing.Protocol`
+    /// - The protocol has no members, since every class would satisfy it
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::SubclassFinder;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// // Find all classes implementing the `SupportsClose` protocol
+    /// let classes = finder.find_protocol_implementations("SupportsClose", None)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_protocol_implementations(
+        &self,
+        protocol_name: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<ClassReference>> {
+        let protocol_id = self.resolve_target_class(protocol_name, module_path)?;
+        let qualified_name = || format!("{}.{}", protocol_id.module, protocol_id.name);
+        if !classify::is_protocol(&self.registry, &protocol_id) {
+            return Err(Error::NotAProtocol {
+                name: qualified_name(),
+            });
+        }
+
+        let required = self.member_names(&protocol_id);
+        let required: Vec<&str> = required
+            .iter()
+            .map(String::as_str)
+            .filter(|name| !PROTOCOL_EXCLUDED_MEMBERS.contains(name))
+            .collect();
+        if required.is_empty() {
+            return Err(Error::EmptyProtocol {
+                name: qualified_name(),
+            });
+        }
+
+        let class_ids: Vec<registry::ClassId> = self
+            .registry
+            .classes
+            .keys()
+            .filter(|class_id| !classify::is_protocol(&self.registry, class_id))
+            .filter(|class_id| **class_id != protocol_id)
+            .filter(|class_id| {
+                let names = self.member_names(class_id);
+                required.iter().all(|name| names.contains(*name))
             })
+            .cloned()
             .collect();
 
-        // Sort by module path for consistent output
-        results.sort_by(|a, b| {
-            a.module_path
-                .cmp(&b.module_path)
-                .then(a.class_name.cmp(&b.class_name))
-        });
+        Ok(self.to_reported_references(&class_ids))
+    }
+
+    /// Finds the subclasses of an abstract base class that don't implement all of the
+    /// abstract methods they inherit.
+    ///
+    /// A method is unimplemented if the first definition of it in the class's method
+    /// resolution order is an abstract method (decorated with `@abstractmethod`). Such
+    /// classes raise `TypeError: Can't instantiate abstract class` when instantiated.
+    ///
+    /// Classes which are evidently meant to be abstract are not reported: classes
+    /// declaring abstract methods of their own, classes listing `ABC` or `Protocol` as
+    /// a base, and classes declaring the `ABCMeta` metaclass. Virtual subclasses are not
+    /// checked, since registering a class doesn't make its abstract methods enforced.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_name` - The simple name of the abstract base class
+    /// * `module_path` - Optional module path to disambiguate the class if the name
+    ///   appears multiple times in the codebase
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of the subclasses with unimplemented abstract methods, listed
+    /// in [`ClassReference::unimplemented_abstract_methods`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The class is not found
+    /// - The class name is ambiguous and no module path is provided
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::SubclassFinder;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// for class_ref in finder.find_unimplemented_abstract_methods("Shape", None)? {
+    ///     for method in &class_ref.unimplemented_abstract_methods {
+    ///         println!("{} doesn't implement {}", class_ref.class_name, method.name);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_unimplemented_abstract_methods(
+        &self,
+        class_name: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<ClassReference>> {
+        let target_id = self.resolve_target_class(class_name, module_path)?;
+
+        let class_ids: Vec<registry::ClassId> = self
+            .graph
+            .find_all_subclasses(&target_id)
+            .into_iter()
+            .filter(|id| !classify::is_declared_abstract(&self.registry, id))
+            .filter(|id| !classify::abstract_methods(&self.registry, &self.graph, id).is_empty())
+            .collect();
+
+        let mut results = self.to_reported_references(&class_ids);
+        for class_ref in &mut results {
+            let id = registry::ClassId {
+                module: class_ref.module_path.clone(),
+                name: class_ref.class_name.clone(),
+            };
+            class_ref.unimplemented_abstract_methods =
+                classify::abstract_methods(&self.registry, &self.graph, &id);
+        }
 
         Ok(results)
     }
 
+    /// Finds the enums deriving from an enum base class.
+    ///
+    /// The base class is either an enum class in the codebase, or one of the enum base
+    /// classes of the `enum` module (`Enum`, `IntEnum`, `StrEnum`, `Flag`, `IntFlag` or
+    /// `ReprEnum`), which needn't be defined in the codebase. Classes are recognised as
+    /// enums through their resolved ancestry, so e.g. searching for `Enum` also finds
+    /// `IntEnum` subclasses, and subclasses of enum classes defined in the codebase.
+    ///
+    /// The members of each enum are listed in [`ClassReference::enum_members`].
+    ///
+    /// # Arguments
+    ///
+    /// * `base_name` - The simple name of the enum base class (e.g. "IntEnum")
+    /// * `module_path` - Optional module path to disambiguate the class if the name
+    ///   appears multiple times in the codebase ("enum" for the `enum` module)
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of the enums deriving from the base class.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The class is neither found in the codebase nor an enum base class of the `enum`
+    ///   module
+    /// - The class name is ambiguous and no module path is provided
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::SubclassFinder;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// for class_ref in finder.find_enums("IntEnum", None)? {
+    ///     for member in &class_ref.enum_members {
+    ///         println!("{}.{}", class_ref.class_name, member.name);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_enums(
+        &self,
+        base_name: &str,
+        module_path: Option<&str>,
+    ) -> Result<Vec<ClassReference>> {
+        let base_id = match self.resolve_target_class(base_name, module_path) {
+            Ok(base_id) => Some(base_id),
+            Err(Error::ClassNotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+
+        // External classes have no parents in the graph, so enums deriving from an enum
+        // base class of the `enum` module indirectly (e.g. `IntEnum` subclasses, which
+        // derive from `Enum`) are found through their ancestry instead
+        let is_enum_module_base = base_id.as_ref().is_none_or(|id| self.graph.is_external(id))
+            && module_path.is_none_or(|module| module == "enum")
+            && classify::is_enum_base(base_name);
+
+        let class_ids: Vec<registry::ClassId> = if is_enum_module_base {
+            let base_name = base_name.rsplit('.').next().unwrap_or(base_name);
+            self.registry
+                .classes
+                .keys()
+                .filter(|id| {
+                    classify::enum_bases(&self.registry, &self.graph, id).contains(&base_name)
+                })
+                .cloned()
+                .collect()
+        } else if let Some(base_id) = base_id {
+            self.graph
+                .find_all_subclasses(&base_id)
+                .into_iter()
+                .filter(|id| self.is_enum(id))
+                .collect()
+        } else {
+            return Err(Error::ClassNotFound {
+                name: base_name.to_string(),
+                module_path: module_path.map(str::to_string),
+            });
+        };
+
+        let mut results = self.to_reported_references(&class_ids);
+        self.list_enum_members(&mut results);
+        Ok(results)
+    }
+
+    /// Finds all enums in the codebase, with their members.
+    ///
+    /// Unlike `find_enums("Enum", None)`, this doesn't resolve any class name, so a class
+    /// named `Enum` defined in the codebase doesn't restrict the results.
+    ///
+    /// # Returns
+    ///
+    /// A sorted vector of all enums.
+    pub fn find_all_enums(&self) -> Vec<ClassReference> {
+        let class_ids: Vec<registry::ClassId> = self
+            .registry
+            .classes
+            .keys()
+            .filter(|id| self.is_enum(id))
+            .cloned()
+            .collect();
+
+        let mut results = self.to_reported_references(&class_ids);
+        self.list_enum_members(&mut results);
+        results
+    }
+
+    /// Returns the fields of a class, including those inherited from its parent classes.
+    ///
+    /// Fields are the annotated class-level attributes (e.g. `x: int = 0`), as used by
+    /// dataclasses, attrs, pydantic models, `NamedTuple`s and `TypedDict`s. They are
+    /// merged the way dataclasses merge them: in reverse MRO order, starting with the
+    /// most distant parent class, with a field redefined by a subclass keeping its
+    /// original position. `ClassVar` attributes and the `KW_ONLY` sentinel are not fields.
+    ///
+    /// Only the classes whose annotations are fields contribute: dataclasses and attrs
+    /// classes (decorated themselves), `NamedTuple`s, and classes deriving from a pydantic
+    /// model or `TypedDict`. The result is empty for other classes, and the annotations of
+    /// plain parent classes (e.g. mixins) are left out.
+    ///
+    /// # Arguments
+    ///
+    /// * `class_name` - The simple name of the class
+    /// * `module_path` - Optional module path to disambiguate the class if the name
+    ///   appears multiple times in the codebase
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The class is not found
+    /// - The class name is ambiguous and no module path is provided
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use pysubclasses::SubclassFinder;
+    /// use std::path::PathBuf;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let finder = SubclassFinder::new(PathBuf::from("."))?;
+    ///
+    /// for field in finder.find_fields("User", Some("models"))? {
+    ///     println!("{}: {} (from {})", field.name, field.annotation, field.defined_in);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_fields(&self, class_name: &str, module_path: Option<&str>) -> Result<Vec<Field>> {
+        let class_id = self.resolve_target_class(class_name, module_path)?;
+        Ok(self.fields(&class_id))
+    }
+
     /// Returns the number of classes found in the codebase.
     pub fn class_count(&self) -> usize {
         self.registry.classes.len()
     }
 
+    /// Returns the files that were only partially parsed because of syntax errors, sorted.
+    ///
+    /// Classes defined in the skipped parts of these files are missing from the results.
+    /// Files are only partially parsed if [`ParseOptions::error_tolerant`] is set;
+    /// otherwise files with syntax errors are skipped entirely.
+    pub fn partially_parsed_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .registry
+            .modules
+            .values()
+            .flat_map(|module| module.partial_files.iter().cloned())
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Returns the modules provided by more than one source or library root, sorted by
+    /// module path.
+    ///
+    /// Each module is only indexed from the first root providing it: source roots in the
+    /// order they were given, then the root directory, then library roots.
+    pub fn module_conflicts(&self) -> &[ModuleConflict] {
+        &self.module_conflicts
+    }
+
     /// Resolves a target class by name and optional module path.
     ///
     /// This helper method encapsulates the logic for finding a class given its name
     /// and optional module path. It handles:
     /// - Module-qualified lookups (with re-export resolution)
     /// - Unqualified lookups by class name
+    /// - External classes (base classes not defined in the codebase), if no class in
+    ///   the codebase matches, looked up by name or fully qualified name
     /// - Ambiguity detection when multiple classes have the same name
     ///
     /// # Arguments
@@ -344,8 +1401,14 @@ impl SubclassFinder {
     ) -> Result<registry::ClassId> {
         if let Some(module) = module_path {
             // Module specified - look for class in that module (including re-exports)
+            let external_id = registry::ClassId {
+                module: module.to_string(),
+                name: class_name.to_string(),
+            };
             if let Some(resolved_id) = self.registry.resolve_class(module, class_name) {
                 Ok(resolved_id)
+            } else if self.graph.is_external(&external_id) {
+                Ok(external_id)
             } else {
                 Err(Error::ClassNotFound {
                     name: class_name.to_string(),
@@ -361,6 +1424,22 @@ impl SubclassFinder {
                 }
             }
 
+            // Fall back to external classes, by name or qualified name
+            if matches.is_empty() {
+                matches.extend(
+                    self.graph
+                        .external_classes()
+                        .filter(|id| {
+                            id.name == class_name
+                                || class_name
+                                    .strip_suffix(id.name.as_str())
+                                    .and_then(|prefix| prefix.strip_suffix('.'))
+                                    == Some(id.module.as_str())
+                        })
+                        .cloned(),
+                );
+            }
+
             match matches.len() {
                 0 => Err(Error::ClassNotFound {
                     name: class_name.to_string(),
@@ -388,12 +1467,295 @@ impl SubclassFinder {
         // Use resolve_target_class for the core logic
         let target_id = self.resolve_target_class(class_name, module_path).ok()?;
 
-        // Convert to ClassReference
-        let metadata = self.registry.modules.get(&target_id.module)?;
+        self.class_reference(&target_id)
+    }
+
+    /// Converts a `ClassId` into a `ClassReference`.
+    fn class_reference(&self, id: &registry::ClassId) -> Option<ClassReference> {
+        if self.graph.is_external(id) && !self.registry.classes.contains_key(id) {
+            return Some(self.external_class_reference(id));
+        }
+        let class = self.registry.classes.get(id)?;
+        let module = self.registry.modules.get(&id.module)?;
         Some(ClassReference {
-            class_name: target_id.name.clone(),
-            module_path: target_id.module.clone(),
-            file_path: metadata.file_path.clone(),
+            class_name: id.name.clone(),
+            module_path: id.module.clone(),
+            file_path: class.file_path.clone(),
+            location: class.location,
+            guard: class.guard.clone(),
+            is_dynamic: class.is_dynamic,
+            is_partial: module.partial_files.contains(&class.file_path),
+            is_virtual: false,
+            metaclass: class.metaclass.clone(),
+            bases: class
+                .bases
+                .iter()
+                .map(|base| BaseClass {
+                    name: self.qualify_name(id, &base.name),
+                    type_args: base
+                        .type_args
+                        .iter()
+                        .map(|arg| self.qualify_name(id, arg))
+                        .collect(),
+                    location: base.location,
+                })
+                .collect(),
+            member_status: None,
+            unimplemented_abstract_methods: Vec::new(),
+            tags: Vec::new(),
+            decorators: class
+                .decorators
+                .iter()
+                .map(|decorator| ClassDecorator {
+                    name: self.registry.qualify_imported_name(id, &decorator.name),
+                    ..decorator.clone()
+                })
+                .collect(),
+            attributes: Vec::new(),
+            fields: Vec::new(),
+            is_library: self.is_library_file(&class.file_path),
+            is_external: false,
+            enum_members: Vec::new(),
         })
     }
+
+    /// Converts the `ClassId` of an external class into a `ClassReference`.
+    fn external_class_reference(&self, id: &registry::ClassId) -> ClassReference {
+        ClassReference {
+            class_name: id.name.clone(),
+            module_path: id.module.clone(),
+            file_path: PathBuf::new(),
+            location: Location {
+                line: 0,
+                column: 0,
+                end_line: 0,
+                end_column: 0,
+                cell: None,
+            },
+            guard: None,
+            is_dynamic: false,
+            is_partial: false,
+            is_virtual: false,
+            metaclass: None,
+            bases: Vec::new(),
+            member_status: None,
+            unimplemented_abstract_methods: Vec::new(),
+            tags: Vec::new(),
+            decorators: Vec::new(),
+            attributes: Vec::new(),
+            fields: Vec::new(),
+            is_library: false,
+            is_external: true,
+            enum_members: Vec::new(),
+        }
+    }
+
+    /// Returns the qualified name of the class `name` refers to in the definition of the
+    /// class `id`, or `name` itself if it doesn't resolve to a class in the codebase.
+    fn qualify_name(&self, id: &registry::ClassId, name: &str) -> String {
+        match self.registry.resolve_base_class(id, name) {
+            Some(resolved) => format!("{}.{}", resolved.module, resolved.name),
+            None => name.to_string(),
+        }
+    }
+
+    /// Returns the (qualified) type arguments the class `id` specialises `target_id` with.
+    ///
+    /// If the class doesn't subscript `target_id` itself, the type arguments are taken
+    /// from the nearest parent class which does. `visited` guards against cycles.
+    fn find_type_args(
+        &self,
+        id: &registry::ClassId,
+        target_id: &registry::ClassId,
+        visited: &mut HashSet<registry::ClassId>,
+    ) -> Option<Vec<String>> {
+        if !visited.insert(id.clone()) {
+            return None;
+        }
+        let class = self.registry.classes.get(id)?;
+
+        let resolved_bases: Vec<_> = class
+            .bases
+            .iter()
+            .filter_map(|base| Some((self.registry.resolve_base_class(id, &base.name)?, base)))
+            .collect();
+
+        for (parent_id, base) in &resolved_bases {
+            if parent_id == target_id && !base.type_args.is_empty() {
+                return Some(
+                    base.type_args
+                        .iter()
+                        .map(|arg| self.qualify_name(id, arg))
+                        .collect(),
+                );
+            }
+        }
+
+        resolved_bases
+            .iter()
+            .filter(|(parent_id, _)| parent_id != target_id)
+            .find_map(|(parent_id, _)| self.find_type_args(parent_id, target_id, visited))
+    }
+
+    /// Returns the names of the members defined by a class or any of its parent classes.
+    fn member_names(&self, id: &registry::ClassId) -> HashSet<String> {
+        std::iter::once(id.clone())
+            .chain(self.graph.find_all_parent_classes(id))
+            .filter_map(|class_id| self.registry.classes.get(&class_id))
+            .flat_map(|class| class.members.iter().map(|member| member.name.clone()))
+            .collect()
+    }
+
+    /// Returns whether the class `id` is an enum.
+    fn is_enum(&self, id: &registry::ClassId) -> bool {
+        !classify::enum_bases(&self.registry, &self.graph, id).is_empty()
+    }
+
+    /// Returns the members of the class `id` if it is an enum.
+    ///
+    /// The members are the attributes assigned in the class body, apart from special
+    /// names (`__dunder__` and `_sunder_` names, and private `__names`) and annotated
+    /// attributes, which the typing spec treats as non-members.
+    fn enum_members(&self, id: &registry::ClassId) -> Vec<EnumMember> {
+        if !self.is_enum(id) {
+            return Vec::new();
+        }
+        let Some(class) = self.registry.classes.get(id) else {
+            return Vec::new();
+        };
+        class
+            .members
+            .iter()
+            .filter(|member| member.kind == MemberKind::Attribute && member.annotation.is_none())
+            .filter(|member| {
+                let name = member.name.as_str();
+                let is_sunder = name.len() > 2 && name.starts_with('_') && name.ends_with('_');
+                !is_sunder && !name.starts_with("__")
+            })
+            .map(|member| EnumMember {
+                name: member.name.clone(),
+                value: member.value.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns the fields of the class `id` (see [`SubclassFinder::find_fields`]).
+    fn fields(&self, id: &registry::ClassId) -> Vec<Field> {
+        let mut fields: Vec<Field> = Vec::new();
+        for class_id in self.graph.mro(id).iter().rev() {
+            let Some(class) = self.registry.classes.get(class_id) else {
+                continue;
+            };
+            // The annotations of plain classes (e.g. mixins and protocols) aren't fields
+            if !classify::declares_fields(&self.registry, &self.graph, class_id) {
+                continue;
+            }
+            for member in &class.members {
+                let Some(annotation) = &member.annotation else {
+                    continue;
+                };
+                let annotation_name = annotation.split('[').next().unwrap_or(annotation);
+                if matches!(
+                    annotation_name.rsplit('.').next(),
+                    Some("ClassVar" | "KW_ONLY")
+                ) {
+                    continue;
+                }
+
+                let field = Field {
+                    name: member.name.clone(),
+                    annotation: annotation.clone(),
+                    default: member.value.clone(),
+                    defined_in: format!("{}.{}", class_id.module, class_id.name),
+                };
+                match fields.iter_mut().find(|f| f.name == field.name) {
+                    Some(existing) => *existing = field,
+                    None => fields.push(field),
+                }
+            }
+        }
+        fields
+    }
+
+    /// Returns the value of the attribute `name` of the class `id`, as defined by the
+    /// class itself or the nearest parent class in its MRO.
+    fn attribute_value(&self, id: &registry::ClassId, name: &str) -> AttributeValue {
+        let definition = self.graph.mro(id).into_iter().find_map(|class_id| {
+            let class = self.registry.classes.get(&class_id)?;
+            let member = class.members.iter().find(|member| member.name == name)?;
+            Some((class_id, member.value.clone()))
+        });
+        match definition {
+            Some((class_id, value)) => AttributeValue {
+                name: name.to_string(),
+                value,
+                defined_in: Some(format!("{}.{}", class_id.module, class_id.name)),
+            },
+            None => AttributeValue {
+                name: name.to_string(),
+                value: None,
+                defined_in: None,
+            },
+        }
+    }
+
+    /// Returns how the class `id` relates to the member `name`.
+    fn member_status(&self, id: &registry::ClassId, name: &str) -> MemberStatus {
+        let defines = |class_id: &registry::ClassId| {
+            self.registry
+                .classes
+                .get(class_id)
+                .is_some_and(|class| class.members.iter().any(|member| member.name == name))
+        };
+        let inherits = self.graph.find_all_parent_classes(id).iter().any(&defines);
+
+        match (defines(id), inherits) {
+            (true, false) => MemberStatus::Defines,
+            (true, true) => MemberStatus::Overrides,
+            (false, true) => MemberStatus::Inherits,
+            (false, false) => MemberStatus::Missing,
+        }
+    }
+
+    /// Converts the `ClassId`s of search results into sorted `ClassReference`s, leaving
+    /// out library classes unless [`FinderOptions::include_library`] is set.
+    fn to_reported_references(&self, ids: &[registry::ClassId]) -> Vec<ClassReference> {
+        let ids: Vec<registry::ClassId> = ids
+            .iter()
+            .filter(|id| self.include_library || !self.is_library_class(id))
+            .cloned()
+            .collect();
+        self.to_sorted_references(&ids)
+    }
+
+    /// Returns whether the class `id` is defined in a library root.
+    fn is_library_class(&self, id: &registry::ClassId) -> bool {
+        self.registry
+            .classes
+            .get(id)
+            .is_some_and(|class| self.is_library_file(&class.file_path))
+    }
+
+    /// Returns whether a file is in a library root.
+    fn is_library_file(&self, file_path: &Path) -> bool {
+        self.library_roots
+            .iter()
+            .any(|library_root| file_path.starts_with(library_root))
+    }
+
+    /// Converts `ClassId`s into `ClassReference`s, sorted by module path for consistent output.
+    fn to_sorted_references(&self, ids: &[registry::ClassId]) -> Vec<ClassReference> {
+        let mut results: Vec<ClassReference> = ids
+            .iter()
+            .filter_map(|id| self.class_reference(id))
+            .collect();
+
+        results.sort_by(|a, b| {
+            a.module_path
+                .cmp(&b.module_path)
+                .then(a.class_name.cmp(&b.class_name))
+        });
+
+        results
+    }
 }
@@ -2,8 +2,12 @@
 
 use anyhow::{Context, Result};
 use clap::Parser;
-use pysubclasses::{ClassReference, SearchMode, SubclassFinder};
+use pysubclasses::{
+    ClassReference, ClassTag, Field, FinderOptions, LiteralValue, Location, MemberStatus,
+    ParseOptions, SearchMode, SearchOptions, StubPolicy, SubclassFinder, discovery,
+};
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 /// Find all subclasses of a Python class
@@ -17,8 +21,10 @@ use std::path::{Path, PathBuf};
 )]
 struct Args {
     /// Name of the class to find subclasses for
-    #[arg()]
-    class_name: String,
+    ///
+    /// May be left out with --enums, to list all enums.
+    #[arg(required_unless_present = "enums")]
+    class_name: Option<String>,
 
     /// Dotted module path where the class is defined (e.g., 'foo.bar'), or a file path
     ///
@@ -34,6 +40,15 @@ struct Args {
     #[arg(short, long, default_value = ".")]
     directory: PathBuf,
 
+    /// Source root of the project's modules (can be specified multiple times)
+    ///
+    /// Module paths are relative to the deepest source root containing the file, e.g.
+    /// `--source-root src` gives `pkg.models` for `src/pkg/models.py`. Paths can be
+    /// relative to the search directory or absolute. Files outside all source roots have
+    /// module paths relative to the search directory.
+    #[arg(long = "source-root", value_name = "DIR")]
+    source_roots: Vec<PathBuf>,
+
     /// Exclude directories from analysis (can be specified multiple times)
     ///
     /// Paths can be relative to the search directory or absolute.
@@ -49,9 +64,143 @@ struct Args {
     #[arg(long, value_enum, default_value = "all")]
     mode: Mode,
 
+    /// Index the packages installed in this Python environment (e.g. `.venv`) as libraries
+    ///
+    /// The classes of installed packages are traversed (e.g. to find the subclasses of
+    /// `django.views.View` inheriting from it through Django REST framework), but only
+    /// reported with --include-library.
+    #[arg(long, value_name = "PATH")]
+    venv: Option<PathBuf>,
+
+    /// Index the packages in this site-packages directory as libraries (can be
+    /// specified multiple times)
+    #[arg(long = "site-packages", value_name = "DIR")]
+    site_packages: Vec<PathBuf>,
+
+    /// Also report classes from the packages indexed with --venv or --site-packages
+    #[arg(long)]
+    include_library: bool,
+
     /// Disable cache (always parse all files)
     #[arg(long)]
     no_cache: bool,
+
+    /// Show the guard of conditionally defined classes in text output
+    ///
+    /// E.g. classes defined under `if TYPE_CHECKING:` or `except ImportError:`.
+    #[arg(long)]
+    show_guards: bool,
+
+    /// Exclude local classes (classes defined inside functions or methods)
+    #[arg(long)]
+    no_local: bool,
+
+    /// Name of a helper call whose arguments are base classes (can be specified multiple times)
+    ///
+    /// E.g. `--base-forwarding-call compat.with_base` treats `Base` as a base class of
+    /// `class Foo(compat.with_base(Base))`. `with_metaclass(Meta, Base)` is always recognised.
+    #[arg(long = "base-forwarding-call", value_name = "NAME")]
+    base_forwarding_calls: Vec<String>,
+
+    /// Treat the class as a metaclass, and find the classes using it
+    ///
+    /// Finds classes whose metaclass is the class (or a subclass of it), including
+    /// classes inheriting the metaclass from a parent class.
+    #[arg(long)]
+    metaclass: bool,
+
+    /// Treat the class as a Protocol, and find the classes implementing it structurally
+    ///
+    /// Finds classes which define or inherit every method and attribute of the protocol,
+    /// whether or not they inherit from it.
+    #[arg(long, conflicts_with = "metaclass")]
+    protocol: bool,
+
+    /// Treat the class as an abstract base class, and report the subclasses which don't
+    /// implement all of its abstract methods
+    ///
+    /// Exits with status 1 if any are found. Subclasses meant to be abstract (declaring
+    /// abstract methods of their own, or inheriting from `ABC` directly) are not reported.
+    #[arg(long, conflicts_with_all = ["metaclass", "protocol"])]
+    check_abstract: bool,
+
+    /// List the enums deriving from the class, with their members
+    ///
+    /// The class may be an enum base class of the `enum` module (e.g. `IntEnum`), even
+    /// though it isn't defined in the codebase. Lists all enums if no class is given.
+    #[arg(long, conflicts_with_all = ["metaclass", "protocol", "check_abstract"])]
+    enums: bool,
+
+    /// Only show subclasses specialising the class with this type argument
+    ///
+    /// E.g. `--type-arg User` finds `class UserRepo(Repository[User])` when searching for
+    /// subclasses of `Repository`.
+    #[arg(long, value_name = "NAME")]
+    type_arg: Option<String>,
+
+    /// Which file to index when a module has both a source file (.py) and a stub file (.pyi)
+    #[arg(long, value_enum, default_value = "merge")]
+    stubs: Stubs,
+
+    /// Salvage classes from files with syntax errors, instead of skipping those files
+    ///
+    /// Top-level statements containing syntax errors are skipped, and the files that could
+    /// only be partially parsed are logged as warnings.
+    #[arg(long)]
+    error_tolerant: bool,
+
+    /// Include virtual subclasses (classes registered with `Base.register(Foo)` or
+    /// `@Base.register`, and their subclasses)
+    #[arg(long)]
+    include_virtual: bool,
+
+    /// Show whether each subclass defines, overrides, inherits or is missing this method
+    /// or attribute
+    #[arg(long, value_name = "NAME")]
+    member: Option<String>,
+
+    /// Only show subclasses with one of these statuses for --member (comma-separated)
+    ///
+    /// E.g. `--member save --member-status overrides` finds the subclasses overriding `save`.
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        requires = "member",
+        value_name = "STATUS"
+    )]
+    member_status: Vec<MemberStatusFilter>,
+
+    /// Only show subclasses of these kinds (comma-separated)
+    ///
+    /// E.g. `--only concrete` leaves out abstract classes, protocols and mixins.
+    #[arg(long, value_enum, value_delimiter = ',', value_name = "KIND")]
+    only: Vec<ClassKind>,
+
+    /// Only show subclasses decorated with this decorator (e.g. `register_backend`)
+    ///
+    /// The decorator and its literal arguments are shown next to each subclass.
+    #[arg(long, value_name = "NAME")]
+    decorated_with: Option<String>,
+
+    /// Only show subclasses not decorated with this decorator (e.g. `register_backend`)
+    #[arg(long, value_name = "NAME")]
+    not_decorated_with: Option<String>,
+
+    /// Show the value of this class attribute for each subclass (can be specified
+    /// multiple times)
+    ///
+    /// Literal values (e.g. `name = "s3"`) are shown, including values inherited from
+    /// the nearest parent class defining the attribute.
+    #[arg(long = "attr", value_name = "NAME")]
+    attributes: Vec<String>,
+
+    /// List the fields of the class and each subclass, including inherited fields
+    ///
+    /// Fields are annotated class attributes (e.g. `x: int = 0`), as used by dataclasses,
+    /// attrs, pydantic models, `NamedTuple` and `TypedDict`.
+    #[arg(long)]
+    fields: bool,
 }
 
 #[derive(Debug, Clone, Copy, clap::ValueEnum)]
@@ -72,11 +221,101 @@ enum Mode {
     All,
 }
 
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum Stubs {
+    /// Index only the stub file
+    PreferStub,
+    /// Index only the source file
+    PreferSource,
+    /// Index both files, reporting classes defined in both from the stub
+    Merge,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum MemberStatusFilter {
+    /// The class defines the member, and none of its parent classes do
+    Defines,
+    /// The class defines the member, overriding a parent class
+    Overrides,
+    /// The class inherits the member from a parent class
+    Inherits,
+    /// Neither the class nor its parent classes define the member
+    Missing,
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+enum ClassKind {
+    /// Classes with abstract methods, or inheriting directly from `ABC`
+    Abstract,
+    /// Classes which aren't abstract, protocols or mixins
+    Concrete,
+    /// Classes named `*Mixin`, or only ever used alongside other bases
+    Mixin,
+    /// Classes inheriting directly from `Protocol`
+    Protocol,
+    /// Enums
+    Enum,
+    /// Dataclasses, attrs classes and pydantic models
+    Dataclass,
+    /// Exception classes
+    Exception,
+}
+
+/// How the listed classes relate to the queried class.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Relation {
+    /// The classes are subclasses of the queried class
+    Subclasses,
+    /// The classes use the queried class as their metaclass
+    MetaclassUsers,
+    /// The classes structurally implement the queried protocol
+    ProtocolImplementations,
+    /// The classes are subclasses not implementing all abstract methods of the queried class
+    UnimplementedAbstractMethods,
+    /// The classes are enums deriving from the queried class
+    Enums,
+}
+
+impl Relation {
+    fn found(self, count: usize, class_name: &str) -> String {
+        match self {
+            Relation::Subclasses => format!("Found {count} subclass(es) of '{class_name}':"),
+            Relation::MetaclassUsers => {
+                format!("Found {count} class(es) using metaclass '{class_name}':")
+            }
+            Relation::ProtocolImplementations => {
+                format!("Found {count} implementation(s) of protocol '{class_name}':")
+            }
+            Relation::UnimplementedAbstractMethods => format!(
+                "Found {count} subclass(es) of '{class_name}' with unimplemented abstract methods:"
+            ),
+            Relation::Enums => format!("Found {count} enum(s) deriving from '{class_name}':"),
+        }
+    }
+
+    fn none_found(self, class_name: &str) -> String {
+        match self {
+            Relation::Subclasses => format!("No subclasses found for '{class_name}'"),
+            Relation::MetaclassUsers => format!("No classes found using metaclass '{class_name}'"),
+            Relation::ProtocolImplementations => {
+                format!("No implementations found for protocol '{class_name}'")
+            }
+            Relation::UnimplementedAbstractMethods => {
+                format!("All subclasses of '{class_name}' implement its abstract methods")
+            }
+            Relation::Enums => format!("No enums found deriving from '{class_name}'"),
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct JsonOutput {
     class_name: String,
     module_path: Option<String>,
+    is_external: bool,
     subclasses: Vec<JsonClass>,
+    partially_parsed_files: Vec<String>,
+    fields: Option<Vec<JsonField>>,
 }
 
 #[derive(Serialize)]
@@ -84,6 +323,98 @@ struct JsonClass {
     class_name: String,
     module_path: String,
     file_path: String,
+    location: JsonLocation,
+    guard: Option<String>,
+    is_dynamic: bool,
+    is_partial: bool,
+    is_stub: bool,
+    is_virtual: bool,
+    is_library: bool,
+    is_external: bool,
+    metaclass: Option<String>,
+    bases: Vec<JsonBase>,
+    member_status: Option<String>,
+    unimplemented_abstract_methods: Vec<JsonAbstractMethod>,
+    tags: Vec<String>,
+    decorators: Vec<JsonDecorator>,
+    attributes: BTreeMap<String, JsonAttribute>,
+    fields: Option<Vec<JsonField>>,
+    enum_members: Vec<JsonEnumMember>,
+}
+
+#[derive(Serialize)]
+struct JsonAttribute {
+    value: serde_json::Value,
+    defined_in: Option<String>,
+}
+
+#[derive(Serialize)]
+struct JsonEnumMember {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct JsonField {
+    name: String,
+    annotation: String,
+    default: serde_json::Value,
+    defined_in: String,
+}
+
+impl From<&Field> for JsonField {
+    fn from(field: &Field) -> Self {
+        Self {
+            name: field.name.clone(),
+            annotation: field.annotation.clone(),
+            default: field
+                .default
+                .as_ref()
+                .map_or(serde_json::Value::Null, literal_to_json),
+            defined_in: field.defined_in.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonDecorator {
+    name: String,
+    args: Vec<serde_json::Value>,
+    keywords: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Serialize)]
+struct JsonAbstractMethod {
+    name: String,
+    declared_in: String,
+}
+
+#[derive(Serialize)]
+struct JsonBase {
+    name: String,
+    type_args: Vec<String>,
+    location: JsonLocation,
+}
+
+#[derive(Serialize)]
+struct JsonLocation {
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+    cell: Option<u32>,
+}
+
+impl From<Location> for JsonLocation {
+    fn from(location: Location) -> Self {
+        Self {
+            line: location.line,
+            column: location.column,
+            end_line: location.end_line,
+            end_column: location.end_column,
+            cell: location.cell,
+        }
+    }
 }
 
 fn main() -> Result<()> {
@@ -92,15 +423,35 @@ fn main() -> Result<()> {
 
     let args = Args::parse();
 
+    // Without a class name, --enums lists all enums, i.e. the enums deriving from `enum.Enum`
+    // (which is only the name shown, so that a class named `Enum` in the codebase isn't used)
+    let all_enums = args.class_name.is_none();
+    let class_name = args.class_name.unwrap_or_else(|| "enum.Enum".to_string());
+
     // Canonicalize the directory path
     let root_dir = args
         .directory
         .canonicalize()
         .with_context(|| format!("Failed to access directory: {}", args.directory.display()))?;
 
+    // Canonicalize the source roots, relative to the search directory
+    let source_roots =
+        args.source_roots
+            .iter()
+            .map(|source_root| {
+                root_dir.join(source_root).canonicalize().with_context(|| {
+                    format!("Failed to access source root: {}", source_root.display())
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
     // Resolve module argument (convert file path to module path if needed)
     let module_path = if let Some(module_arg) = &args.module {
-        Some(resolve_module_argument(module_arg, &root_dir)?)
+        Some(resolve_module_argument(
+            module_arg,
+            &root_dir,
+            &source_roots,
+        )?)
     } else {
         None
     };
@@ -113,14 +464,43 @@ fn main() -> Result<()> {
         log::debug!("Cache disabled");
     }
 
+    // Find the library roots to index besides the project
+    let mut library_roots = args.site_packages;
+    if let Some(venv) = &args.venv {
+        library_roots.extend(
+            discovery::find_site_packages(venv).with_context(|| {
+                format!("Failed to access Python environment: {}", venv.display())
+            })?,
+        );
+    }
+    for library_root in &library_roots {
+        log::debug!("Indexing library root: {}", library_root.display());
+    }
+
     // Create the finder (this parses all Python files)
-    let finder = SubclassFinder::with_options(root_dir, args.exclude, !args.no_cache)
+    let finder_options = FinderOptions {
+        exclude_dirs: args.exclude,
+        use_cache: !args.no_cache,
+        parse_options: ParseOptions {
+            base_forwarding_calls: args.base_forwarding_calls,
+            error_tolerant: args.error_tolerant,
+        },
+        stub_policy: match args.stubs {
+            Stubs::PreferStub => StubPolicy::PreferStub,
+            Stubs::PreferSource => StubPolicy::PreferSource,
+            Stubs::Merge => StubPolicy::Merge,
+        },
+        source_roots,
+        library_roots,
+        include_library: args.include_library,
+    };
+    let finder = SubclassFinder::from_options(root_dir.clone(), finder_options)
         .context("Failed to analyze codebase")?;
 
     log::debug!("Found {} classes in codebase", finder.class_count());
     log::debug!(
         "Searching for subclasses of '{}'{}",
-        args.class_name,
+        class_name,
         module_path
             .as_ref()
             .map(|m| format!(" in module '{m}'"))
@@ -133,45 +513,203 @@ fn main() -> Result<()> {
         Mode::All => SearchMode::All,
     };
 
-    // Find subclasses
-    let subclasses = finder
-        .find_subclasses(&args.class_name, module_path.as_deref(), mode)
-        .map_err(|e| match &e {
-            pysubclasses::Error::AmbiguousClassName { name, candidates } => {
-                let formatted_candidates = candidates
-                    .iter()
-                    .map(|c| format!("  - {c}"))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                anyhow::anyhow!(
-                    "Class '{name}' found in multiple modules:\n{formatted_candidates}\n\nPlease specify --module to disambiguate."
-                )
-            }
-            _ => anyhow::Error::from(e),
-        })
-        .context("Failed to find subclasses")?;
+    // Tags and enum members are only worth computing for JSON output (and `--only`)
+    let json = matches!(args.format, OutputFormat::Json);
+    let options = SearchOptions {
+        include_local: !args.no_local,
+        type_arg: args.type_arg,
+        include_virtual: args.include_virtual,
+        member: args.member.clone(),
+        member_statuses: args
+            .member_status
+            .iter()
+            .map(|status| match status {
+                MemberStatusFilter::Defines => MemberStatus::Defines,
+                MemberStatusFilter::Overrides => MemberStatus::Overrides,
+                MemberStatusFilter::Inherits => MemberStatus::Inherits,
+                MemberStatusFilter::Missing => MemberStatus::Missing,
+            })
+            .collect(),
+        only: args
+            .only
+            .iter()
+            .map(|kind| match kind {
+                ClassKind::Abstract => ClassTag::Abstract,
+                ClassKind::Concrete => ClassTag::Concrete,
+                ClassKind::Mixin => ClassTag::Mixin,
+                ClassKind::Protocol => ClassTag::Protocol,
+                ClassKind::Enum => ClassTag::Enum,
+                ClassKind::Dataclass => ClassTag::Dataclass,
+                ClassKind::Exception => ClassTag::Exception,
+            })
+            .collect(),
+        include_tags: json,
+        include_enum_members: json,
+        decorated_with: args.decorated_with.clone(),
+        not_decorated_with: args.not_decorated_with,
+        attributes: args.attributes,
+        include_fields: args.fields,
+    };
+
+    // Find the requested classes
+    let (relation, mut subclasses) = if args.metaclass {
+        let classes = finder
+            .find_classes_with_metaclass(&class_name, module_path.as_deref())
+            .map_err(explain_error)
+            .context("Failed to find classes using metaclass")?;
+        (Relation::MetaclassUsers, classes)
+    } else if args.protocol {
+        let classes = finder
+            .find_protocol_implementations(&class_name, module_path.as_deref())
+            .map_err(explain_error)
+            .context("Failed to find protocol implementations")?;
+        (Relation::ProtocolImplementations, classes)
+    } else if args.check_abstract {
+        let classes = finder
+            .find_unimplemented_abstract_methods(&class_name, module_path.as_deref())
+            .map_err(explain_error)
+            .context("Failed to check abstract methods")?;
+        (Relation::UnimplementedAbstractMethods, classes)
+    } else if all_enums {
+        (Relation::Enums, finder.find_all_enums())
+    } else if args.enums {
+        let classes = finder
+            .find_enums(&class_name, module_path.as_deref())
+            .map_err(explain_error)
+            .context("Failed to find enums")?;
+        (Relation::Enums, classes)
+    } else {
+        let subclasses = finder
+            .find_subclasses_with_options(&class_name, module_path.as_deref(), mode, &options)
+            .map_err(explain_error)
+            .context("Failed to find subclasses")?;
+        (Relation::Subclasses, subclasses)
+    };
+    if json && relation != Relation::Subclasses {
+        finder.classify_references(&mut subclasses);
+        if relation != Relation::Enums {
+            finder.list_enum_members(&mut subclasses);
+        }
+    }
+
+    // Warn about modules provided by more than one root, since only one is indexed
+    for conflict in finder.module_conflicts() {
+        let relative = |path: &Path| {
+            path.strip_prefix(&root_dir)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        };
+        log::warn!(
+            "Module '{}' is provided by both {} and {}; only the former is indexed",
+            conflict.module_path,
+            relative(&conflict.indexed_file),
+            relative(&conflict.ignored_file)
+        );
+    }
+
+    // Warn that classes may be missing from partially parsed files
+    let partially_parsed_files = finder.partially_parsed_files();
+    for file in &partially_parsed_files {
+        log::warn!(
+            "{} has syntax errors and was only partially parsed",
+            file.strip_prefix(&root_dir).unwrap_or(file).display()
+        );
+    }
+
+    // Resolve the queried class, for describing it in the output
+    let target = if all_enums {
+        None
+    } else {
+        finder.resolve_class_reference(&class_name, module_path.as_deref())
+    };
 
     // Output results
     match args.format {
-        OutputFormat::Text => output_text(&args.class_name, &subclasses),
-        OutputFormat::Json => output_json(&args.class_name, &module_path, &subclasses)?,
-        OutputFormat::Dot => output_dot(&args.class_name, &module_path, &subclasses, &finder)?,
+        OutputFormat::Text => output_text(
+            &class_name,
+            target.as_ref(),
+            relation,
+            &subclasses,
+            &root_dir,
+            &TextAnnotations {
+                show_guards: args.show_guards,
+                member: args.member.as_deref(),
+                decorator: args.decorated_with.as_deref(),
+                enum_members: args.enums,
+            },
+        ),
+        OutputFormat::Json => {
+            let fields = if args.fields && !all_enums {
+                Some(
+                    finder
+                        .find_fields(&class_name, module_path.as_deref())
+                        .map_err(explain_error)?,
+                )
+            } else {
+                None
+            };
+            output_json(
+                &class_name,
+                &module_path,
+                target.as_ref(),
+                &subclasses,
+                &partially_parsed_files,
+                fields.as_deref(),
+            )?
+        }
+        OutputFormat::Dot => output_dot(
+            &class_name,
+            target.as_ref(),
+            relation,
+            &subclasses,
+            &root_dir,
+            &finder,
+        )?,
+    }
+
+    // Fail the check if any subclass can't be instantiated
+    if relation == Relation::UnimplementedAbstractMethods && !subclasses.is_empty() {
+        std::process::exit(1);
     }
 
     Ok(())
 }
 
+/// Adds guidance to errors that the user can fix by changing the arguments.
+fn explain_error(e: pysubclasses::Error) -> anyhow::Error {
+    match &e {
+        pysubclasses::Error::AmbiguousClassName { name, candidates } => {
+            let formatted_candidates = candidates
+                .iter()
+                .map(|c| format!("  - {c}"))
+                .collect::<Vec<_>>()
+                .join("\n");
+            anyhow::anyhow!(
+                "Class '{name}' found in multiple modules:\n{formatted_candidates}\n\nPlease specify --module to disambiguate."
+            )
+        }
+        _ => anyhow::Error::from(e),
+    }
+}
+
 /// Converts a module argument (which may be a file path or module path) to a module path string.
 ///
 /// # Arguments
 ///
 /// * `module_arg` - The module argument from the CLI (either a dotted path or file path)
 /// * `root_dir` - The canonicalized root directory for the search
+/// * `source_roots` - The canonicalized source roots, which file paths are converted
+///   relative to in preference to the root directory
 ///
 /// # Returns
 ///
 /// Either the original string (if it's a dotted path) or the converted module path (if it's a file path).
-fn resolve_module_argument(module_arg: &str, root_dir: &Path) -> Result<String> {
+fn resolve_module_argument(
+    module_arg: &str,
+    root_dir: &Path,
+    source_roots: &[PathBuf],
+) -> Result<String> {
     // If it contains path separators, treat it as a file path
     if module_arg.contains('/') || module_arg.contains('\\') {
         let path = Path::new(module_arg);
@@ -188,8 +726,9 @@ fn resolve_module_argument(module_arg: &str, root_dir: &Path) -> Result<String>
             .canonicalize()
             .with_context(|| format!("Failed to access file: {}", abs_path.display()))?;
 
-        // Convert to module path
-        let module_path = pysubclasses::parser::file_path_to_module_path(&canonical_path, root_dir)
+        // Convert to module path, relative to the source root containing the file
+        let source_root = discovery::owning_root(&canonical_path, source_roots).unwrap_or(root_dir);
+        let module_path = pysubclasses::parser::file_path_to_module_path(&canonical_path, source_root)
             .with_context(|| {
                 format!(
                     "Failed to convert file path '{}' to module path (is it within the search directory '{}'?)",
@@ -205,39 +744,226 @@ fn resolve_module_argument(module_arg: &str, root_dir: &Path) -> Result<String>
     }
 }
 
-fn output_text(class_name: &str, subclasses: &[ClassReference]) {
+/// What to show next to each class in text output, besides its name and position.
+struct TextAnnotations<'a> {
+    /// Whether to show the guards classes are defined under
+    show_guards: bool,
+    /// The member to show the status of
+    member: Option<&'a str>,
+    /// The decorator to show, with its arguments
+    decorator: Option<&'a str>,
+    /// Whether to show the members of enums
+    enum_members: bool,
+}
+
+fn output_text(
+    class_name: &str,
+    target: Option<&ClassReference>,
+    relation: Relation,
+    subclasses: &[ClassReference],
+    root_dir: &Path,
+    annotations: &TextAnnotations,
+) {
     if subclasses.is_empty() {
-        println!("No subclasses found for '{class_name}'");
+        println!("{}", relation.none_found(class_name));
         return;
     }
 
-    println!(
-        "Found {} subclass(es) of '{}':\n",
-        subclasses.len(),
-        class_name
-    );
+    let header = relation.found(subclasses.len(), class_name);
+    match target.filter(|target| target.is_external) {
+        Some(target) => println!(
+            "{} (external class {}):\n",
+            header.trim_end_matches(':'),
+            target.qualified_name()
+        ),
+        None => println!("{header}\n"),
+    }
+
+    let target_name = target.map(|target| target.qualified_name());
 
     for class_ref in subclasses {
-        println!("  {} ({})", class_ref.class_name, class_ref.module_path);
+        let mut line = format!("  {} ({})", class_ref.class_name, class_ref.module_path);
+
+        // Show how the class specialises the queried class, e.g. `Repository[models.User]`
+        if let Some(base) = class_ref
+            .bases
+            .iter()
+            .find(|base| Some(&base.name) == target_name.as_ref() && !base.type_args.is_empty())
+        {
+            line.push_str(&format!(": {}[{}]", class_name, base.type_args.join(", ")));
+        }
+
+        if class_ref.is_virtual {
+            line.push_str(" [virtual]");
+        }
+
+        if class_ref.is_library {
+            line.push_str(" [library]");
+        }
+
+        if let (Some(status), Some(member)) = (class_ref.member_status, annotations.member) {
+            line.push_str(&format!(" [{status} {member}]"));
+        }
+
+        if let Some(decorator) = annotations
+            .decorator
+            .and_then(|name| class_ref.find_decorator(name))
+        {
+            line.push_str(&format!(" [{decorator}]"));
+        }
+
+        if !class_ref.attributes.is_empty() {
+            let attributes: Vec<String> = class_ref
+                .attributes
+                .iter()
+                .map(
+                    |attribute| match (&attribute.value, &attribute.defined_in) {
+                        (Some(value), _) => format!("{}={value}", attribute.name),
+                        (None, Some(_)) => format!("{}=<non-literal>", attribute.name),
+                        (None, None) => format!("{}=<unset>", attribute.name),
+                    },
+                )
+                .collect();
+            line.push_str(&format!(" [{}]", attributes.join(" ")));
+        }
+
+        if !class_ref.fields.is_empty() {
+            let fields: Vec<&str> = class_ref
+                .fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect();
+            line.push_str(&format!(" [fields: {}]", fields.join(", ")));
+        }
+
+        if annotations.enum_members && !class_ref.enum_members.is_empty() {
+            let members: Vec<String> = class_ref
+                .enum_members
+                .iter()
+                .map(|member| match &member.value {
+                    Some(value) => format!("{}={value}", member.name),
+                    None => member.name.clone(),
+                })
+                .collect();
+            line.push_str(&format!(" [members: {}]", members.join(", ")));
+        }
+
+        if !class_ref.unimplemented_abstract_methods.is_empty() {
+            let methods: Vec<&str> = class_ref
+                .unimplemented_abstract_methods
+                .iter()
+                .map(|method| method.name.as_str())
+                .collect();
+            line.push_str(&format!(" [unimplemented: {}]", methods.join(", ")));
+        }
+
+        if let Some(guard) = &class_ref.guard
+            && annotations.show_guards
+        {
+            line.push_str(&format!(" [{guard}]"));
+        }
+
+        line.push_str(&format!(" {}", source_position(class_ref, root_dir)));
+
+        println!("{line}");
     }
 }
 
 fn output_json(
     class_name: &str,
     module_path: &Option<String>,
+    target: Option<&ClassReference>,
     subclasses: &[ClassReference],
+    partially_parsed_files: &[PathBuf],
+    fields: Option<&[Field]>,
 ) -> Result<()> {
     let output = JsonOutput {
         class_name: class_name.to_string(),
         module_path: module_path.clone(),
+        is_external: target.is_some_and(|target| target.is_external),
         subclasses: subclasses
             .iter()
             .map(|c| JsonClass {
                 class_name: c.class_name.clone(),
                 module_path: c.module_path.clone(),
                 file_path: c.file_path.display().to_string(),
+                location: c.location.into(),
+                guard: c.guard.clone(),
+                is_dynamic: c.is_dynamic,
+                is_partial: c.is_partial,
+                is_stub: c.is_stub(),
+                is_virtual: c.is_virtual,
+                is_library: c.is_library,
+                is_external: c.is_external,
+                metaclass: c.metaclass.clone(),
+                bases: c
+                    .bases
+                    .iter()
+                    .map(|b| JsonBase {
+                        name: b.name.clone(),
+                        type_args: b.type_args.clone(),
+                        location: b.location.into(),
+                    })
+                    .collect(),
+                member_status: c.member_status.map(|status| status.to_string()),
+                unimplemented_abstract_methods: c
+                    .unimplemented_abstract_methods
+                    .iter()
+                    .map(|method| JsonAbstractMethod {
+                        name: method.name.clone(),
+                        declared_in: method.declared_in.clone(),
+                    })
+                    .collect(),
+                tags: c.tags.iter().map(|tag| tag.to_string()).collect(),
+                decorators: c
+                    .decorators
+                    .iter()
+                    .map(|decorator| JsonDecorator {
+                        name: decorator.name.clone(),
+                        args: decorator.args.iter().map(literal_to_json).collect(),
+                        keywords: decorator
+                            .keywords
+                            .iter()
+                            .map(|(name, value)| (name.clone(), literal_to_json(value)))
+                            .collect(),
+                    })
+                    .collect(),
+                attributes: c
+                    .attributes
+                    .iter()
+                    .map(|attribute| {
+                        let value = attribute
+                            .value
+                            .as_ref()
+                            .map_or(serde_json::Value::Null, literal_to_json);
+                        (
+                            attribute.name.clone(),
+                            JsonAttribute {
+                                value,
+                                defined_in: attribute.defined_in.clone(),
+                            },
+                        )
+                    })
+                    .collect(),
+                fields: fields.map(|_| c.fields.iter().map(JsonField::from).collect()),
+                enum_members: c
+                    .enum_members
+                    .iter()
+                    .map(|member| JsonEnumMember {
+                        name: member.name.clone(),
+                        value: member
+                            .value
+                            .as_ref()
+                            .map_or(serde_json::Value::Null, literal_to_json),
+                    })
+                    .collect(),
             })
             .collect(),
+        partially_parsed_files: partially_parsed_files
+            .iter()
+            .map(|f| f.display().to_string())
+            .collect(),
+        fields: fields.map(|fields| fields.iter().map(JsonField::from).collect()),
     };
 
     println!("{}", serde_json::to_string_pretty(&output)?);
@@ -246,23 +972,28 @@ fn output_json(
 
 fn output_dot(
     class_name: &str,
-    module_path: &Option<String>,
+    base_class: Option<&ClassReference>,
+    relation: Relation,
     subclasses: &[ClassReference],
+    root_dir: &Path,
     finder: &SubclassFinder,
 ) -> Result<()> {
     use std::collections::HashSet;
 
-    // Resolve the actual base class
-    let base_class = finder
-        .resolve_class_reference(class_name, module_path.as_deref())
-        .with_context(|| format!("Failed to resolve base class '{}'", class_name))?;
+    // Enums may derive from a base class of the `enum` module instead of a class that
+    // can be resolved
+    if base_class.is_none() && relation != Relation::Enums {
+        anyhow::bail!("Failed to resolve base class '{}'", class_name);
+    }
 
     // Create a set of all classes in our graph for quick lookup
     let mut class_set = HashSet::new();
-    class_set.insert((
-        base_class.class_name.clone(),
-        base_class.module_path.clone(),
-    ));
+    if let Some(base_class) = base_class {
+        class_set.insert((
+            base_class.class_name.clone(),
+            base_class.module_path.clone(),
+        ));
+    }
     for subclass in subclasses {
         class_set.insert((subclass.class_name.clone(), subclass.module_path.clone()));
     }
@@ -273,15 +1004,44 @@ fn output_dot(
     println!();
 
     // Add base class node
-    let base_node_id = format!(
-        "{}_{}",
-        sanitize_for_dot(&base_class.module_path),
-        sanitize_for_dot(&base_class.class_name)
-    );
-    println!(
-        "  {} [label=\"{}\\n({})\", fillcolor=lightgreen];",
-        base_node_id, base_class.class_name, base_class.module_path
-    );
+    let base_node_id = match base_class {
+        Some(base_class) if base_class.is_external => {
+            let base_node_id = format!(
+                "{}_{}",
+                sanitize_for_dot(&base_class.module_path),
+                sanitize_for_dot(&base_class.class_name)
+            );
+            println!(
+                "  {} [label=\"{}\\n({})\", tooltip=\"external\", fillcolor=lightgrey];",
+                base_node_id, base_class.class_name, base_class.module_path
+            );
+            base_node_id
+        }
+        Some(base_class) => {
+            let base_node_id = format!(
+                "{}_{}",
+                sanitize_for_dot(&base_class.module_path),
+                sanitize_for_dot(&base_class.class_name)
+            );
+            println!(
+                "  {} [label=\"{}\\n({})\", tooltip=\"{}\", fillcolor=lightgreen];",
+                base_node_id,
+                base_class.class_name,
+                base_class.module_path,
+                source_position(base_class, root_dir)
+            );
+            base_node_id
+        }
+        None => {
+            let base_name = class_name.rsplit('.').next().unwrap_or(class_name);
+            let base_node_id = format!("enum_{}", sanitize_for_dot(base_name));
+            println!(
+                "  {} [label=\"{}\\n(enum)\", fillcolor=lightgrey];",
+                base_node_id, base_name
+            );
+            base_node_id
+        }
+    };
 
     // Add subclass nodes
     for subclass in subclasses {
@@ -291,8 +1051,11 @@ fn output_dot(
             sanitize_for_dot(&subclass.class_name)
         );
         println!(
-            "  {} [label=\"{}\\n({})\"];",
-            node_id, subclass.class_name, subclass.module_path
+            "  {} [label=\"{}\\n({})\", tooltip=\"{}\"];",
+            node_id,
+            subclass.class_name,
+            subclass.module_path,
+            source_position(subclass, root_dir)
         );
     }
 
@@ -301,16 +1064,18 @@ fn output_dot(
     // Add edges
     for subclass in subclasses {
         let parents = finder
-            .find_parent_classes(
+            .find_parent_classes_with_external(
                 &subclass.class_name,
                 Some(&subclass.module_path),
                 SearchMode::Direct,
             )
             .unwrap_or_default();
 
+        let mut has_parent_in_graph = false;
         for parent in parents {
             // Only draw edge if parent is in our class set (either base or another subclass)
             if class_set.contains(&(parent.class_name.clone(), parent.module_path.clone())) {
+                has_parent_in_graph = true;
                 let parent_node_id = format!(
                     "{}_{}",
                     sanitize_for_dot(&parent.module_path),
@@ -324,12 +1089,106 @@ fn output_dot(
                 println!("  {} -> {};", parent_node_id, child_node_id);
             }
         }
+
+        // Link virtual subclasses to the classes they are registered with
+        let virtual_parents = finder
+            .find_virtual_parent_classes(&subclass.class_name, Some(&subclass.module_path))
+            .unwrap_or_default();
+        for parent in virtual_parents {
+            if class_set.contains(&(parent.class_name.clone(), parent.module_path.clone())) {
+                let parent_node_id = format!(
+                    "{}_{}",
+                    sanitize_for_dot(&parent.module_path),
+                    sanitize_for_dot(&parent.class_name)
+                );
+                let child_node_id = format!(
+                    "{}_{}",
+                    sanitize_for_dot(&subclass.module_path),
+                    sanitize_for_dot(&subclass.class_name)
+                );
+                println!(
+                    "  {} -> {} [style=dotted, label=\"register\"];",
+                    parent_node_id, child_node_id
+                );
+            }
+        }
+
+        // Link classes declaring the metaclass to it
+        if relation == Relation::MetaclassUsers && subclass.metaclass.is_some() {
+            let child_node_id = format!(
+                "{}_{}",
+                sanitize_for_dot(&subclass.module_path),
+                sanitize_for_dot(&subclass.class_name)
+            );
+            println!("  {} -> {} [style=dashed];", base_node_id, child_node_id);
+        }
+
+        // Link implementations to the protocol, unless they implement it via a parent
+        if relation == Relation::ProtocolImplementations && !has_parent_in_graph {
+            let child_node_id = format!(
+                "{}_{}",
+                sanitize_for_dot(&subclass.module_path),
+                sanitize_for_dot(&subclass.class_name)
+            );
+            println!(
+                "  {} -> {} [style=dashed, label=\"implements\"];",
+                base_node_id, child_node_id
+            );
+        }
+
+        // Link enums to the `enum` module base class they derive from
+        if relation == Relation::Enums && !has_parent_in_graph {
+            let child_node_id = format!(
+                "{}_{}",
+                sanitize_for_dot(&subclass.module_path),
+                sanitize_for_dot(&subclass.class_name)
+            );
+            println!("  {} -> {};", base_node_id, child_node_id);
+        }
     }
 
     println!("}}");
     Ok(())
 }
 
+/// Converts a literal value to JSON.
+///
+/// Numbers which JSON can't represent (e.g. complex numbers) are given as strings, and
+/// expressions which aren't literals as `{"expr": "..."}` objects.
+fn literal_to_json(value: &LiteralValue) -> serde_json::Value {
+    match value {
+        LiteralValue::Str(value) => value.clone().into(),
+        LiteralValue::Number(value) => value
+            .parse::<i64>()
+            .map(serde_json::Value::from)
+            .ok()
+            .or_else(|| {
+                value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(serde_json::Number::from_f64)
+                    .map(serde_json::Value::Number)
+            })
+            .unwrap_or_else(|| value.clone().into()),
+        LiteralValue::Bool(value) => (*value).into(),
+        LiteralValue::None => serde_json::Value::Null,
+        LiteralValue::Tuple(values) | LiteralValue::List(values) => {
+            values.iter().map(literal_to_json).collect()
+        }
+        LiteralValue::Expr(source) => serde_json::json!({ "expr": source }),
+    }
+}
+
+/// Formats the position of a class definition as `path:line:column`, with the path
+/// relative to the search directory where possible.
+fn source_position(class_ref: &ClassReference, root_dir: &Path) -> String {
+    let path = class_ref
+        .file_path
+        .strip_prefix(root_dir)
+        .unwrap_or(&class_ref.file_path);
+    format!("{}:{}", path.display(), class_ref.location)
+}
+
 fn sanitize_for_dot(s: &str) -> String {
     s.replace(['.', '-'], "_")
 }
@@ -0,0 +1,154 @@
+//! Jupyter notebook support.
+//!
+//! Notebooks (`.ipynb` files) are JSON documents made up of cells. The code cells are
+//! concatenated into a single Python source, which is parsed like any other Python file.
+//! Locations are then mapped back to the cell they came from.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::parser::Location;
+
+/// The Python source extracted from a notebook.
+#[derive(Debug, Clone)]
+pub struct NotebookSource {
+    /// The concatenated source of the notebook's code cells
+    pub source: String,
+    /// The first line (one-indexed) of each code cell within `source`, together with
+    /// the index of the cell in the notebook, in order
+    cell_starts: Vec<(u32, u32)>,
+}
+
+impl NotebookSource {
+    /// Maps a location in the concatenated source to a location within its cell.
+    pub fn map_location(&self, location: Location) -> Location {
+        let Some(&(first_line, cell)) = self
+            .cell_starts
+            .iter()
+            .rev()
+            .find(|(first_line, _)| *first_line <= location.line)
+        else {
+            return location;
+        };
+        Location {
+            line: location.line - first_line + 1,
+            end_line: location.end_line.saturating_sub(first_line) + 1,
+            cell: Some(cell),
+            ..location
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Notebook {
+    cells: Vec<Cell>,
+    #[serde(default)]
+    metadata: NotebookMetadata,
+}
+
+#[derive(Deserialize, Default)]
+struct NotebookMetadata {
+    language_info: Option<LanguageInfo>,
+}
+
+#[derive(Deserialize)]
+struct LanguageInfo {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct Cell {
+    cell_type: String,
+    #[serde(default)]
+    source: CellSource,
+}
+
+/// A cell's source, which nbformat allows to be either a string or a list of lines.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CellSource {
+    Lines(Vec<String>),
+    Text(String),
+}
+
+impl Default for CellSource {
+    fn default() -> Self {
+        CellSource::Text(String::new())
+    }
+}
+
+impl CellSource {
+    fn text(self) -> String {
+        match self {
+            CellSource::Lines(lines) => lines.concat(),
+            CellSource::Text(text) => text,
+        }
+    }
+}
+
+/// Returns whether a file is a Jupyter notebook (`.ipynb`).
+pub fn is_notebook_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext == "ipynb")
+}
+
+/// Extracts the Python source of a notebook from its JSON contents.
+///
+/// Code cells are concatenated in order. IPython syntax that isn't valid Python is
+/// skipped: cells starting with a cell magic (`%%time`) are left out entirely, and
+/// line magics (`%matplotlib inline`) and shell escapes (`!pip install foo`) are
+/// replaced with `pass`, so that line numbers are preserved.
+///
+/// Notebooks for a kernel in a language other than Python have no source.
+///
+/// # Errors
+///
+/// Returns an error if the contents are not a valid notebook.
+pub fn extract_source(contents: &str) -> serde_json::Result<NotebookSource> {
+    let notebook: Notebook = serde_json::from_str(contents)?;
+
+    let mut source = String::new();
+    let mut cell_starts = Vec::new();
+
+    if notebook
+        .metadata
+        .language_info
+        .is_some_and(|language| !language.name.eq_ignore_ascii_case("python"))
+    {
+        return Ok(NotebookSource {
+            source,
+            cell_starts,
+        });
+    }
+
+    let mut next_line = 1;
+    for (index, cell) in notebook.cells.into_iter().enumerate() {
+        if cell.cell_type != "code" {
+            continue;
+        }
+        let text = cell.source.text();
+        if text.trim_start().starts_with("%%") {
+            continue;
+        }
+
+        cell_starts.push((next_line, index as u32));
+        for line in text.lines() {
+            let code = line.trim_start();
+            if code.starts_with('%') || code.starts_with('!') {
+                source.push_str(&line[..line.len() - code.len()]);
+                source.push_str("pass");
+            } else {
+                source.push_str(line);
+            }
+            source.push('\n');
+            next_line += 1;
+        }
+    }
+
+    Ok(NotebookSource {
+        source,
+        cell_starts,
+    })
+}
@@ -1,12 +1,16 @@
 //! Python AST parsing module for extracting class definitions and imports.
 
 use rayon::prelude::*;
-use ruff_python_ast::{Expr, Stmt};
+use ruff_python_ast::{Decorator, ExceptHandler, Expr, Operator, Stmt, StmtClassDef, UnaryOp};
 use ruff_python_parser::parse_module;
-use std::fs;
+use ruff_source_file::LineIndex;
+use ruff_text_size::{Ranged, TextSize};
+use std::fmt;
 use std::path::{Path, PathBuf};
 
+use crate::encoding;
 use crate::error::{Error, Result};
+use crate::notebook;
 
 /// Represents a Python class definition.
 #[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
@@ -17,8 +21,221 @@ pub struct ClassDefinition {
     pub module_path: String,
     /// The file path where the class is defined
     pub file_path: PathBuf,
-    /// The base classes this class inherits from e.g. "Foo" or "foo.Foo"
-    pub bases: Vec<String>,
+    /// The base classes this class inherits from
+    pub bases: Vec<BaseClass>,
+    /// The guard(s) the class is defined under, if any
+    /// (e.g. "if TYPE_CHECKING" or "try, except ImportError")
+    pub guard: Option<String>,
+    /// Whether the class is created dynamically, e.g. `Foo = type("Foo", (Base,), {})`
+    pub is_dynamic: bool,
+    /// The metaclass of this class as written, e.g. "Meta" or "abc.ABCMeta", if declared
+    pub metaclass: Option<String>,
+    /// The location of the class statement (or assignment, for dynamic classes)
+    pub location: Location,
+    /// The methods and attributes defined in the class body
+    pub members: Vec<ClassMember>,
+    /// The decorators applied to the class
+    pub decorators: Vec<ClassDecorator>,
+}
+
+/// A range in a source file, as one-indexed lines and columns.
+///
+/// Columns count characters (not bytes) from the start of the line. In notebooks, lines
+/// are counted from the start of the cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct Location {
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// The (zero-indexed) index of the notebook cell, for locations in notebooks
+    pub cell: Option<u32>,
+}
+
+impl fmt::Display for Location {
+    /// Formats the start of the range as `line:column` (`cell<N>:line:column` in notebooks).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cell) = self.cell {
+            write!(f, "cell{cell}:")?;
+        }
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A base class of a class definition.
+///
+/// E.g.
+/// `class Foo(Bar)` => { name=Bar, type_args=[] }
+/// `class Foo(pkg.Bar)` => { name=pkg.Bar, type_args=[] }
+/// `class Foo(Repository[User])` => { name=Repository, type_args=[User] }
+/// `class Foo(Mapping[str, list[int]])` => { name=Mapping, type_args=[str, list[int]] }
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct BaseClass {
+    /// The base class as written, without any subscript (e.g. "Foo" or "foo.Foo")
+    pub name: String,
+    /// The type arguments the base class is subscripted with, as written
+    ///
+    /// Names and attributes are recorded as dotted names (e.g. "models.User"), string
+    /// forward references by their contents, and other expressions by their source text.
+    pub type_args: Vec<String>,
+    /// The location of the base class expression
+    pub location: Location,
+}
+
+/// A member defined in a class body.
+///
+/// E.g.
+/// `def area(self): ...` => { name=area, kind=Method, decorators=[] }
+/// `@property def area(self): ...` => { name=area, kind=Method, decorators=[property] }
+/// `@abc.abstractmethod def area(self): ...` => { name=area, kind=Method, decorators=[abc.abstractmethod] }
+/// `sides = 4` => { name=sides, kind=Attribute, decorators=[], value=4, annotation=None }
+/// `name: str` => { name=name, kind=Attribute, decorators=[], value=None, annotation=str }
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct ClassMember {
+    pub name: String,
+    pub kind: MemberKind,
+    /// The names of the decorators applied to a method, as written and without any
+    /// arguments (e.g. "property", "functools.cache" or "area.setter")
+    pub decorators: Vec<String>,
+    /// The value an attribute is assigned, if it is a literal (e.g. `"s3"`, `10`, `True`
+    /// or `("a", "b")`)
+    pub value: Option<LiteralValue>,
+    /// The type annotation of an annotated attribute, as written (e.g. "list[str]")
+    pub annotation: Option<String>,
+}
+
+impl ClassMember {
+    /// Returns whether the member is a property (`@property`, `@functools.cached_property`
+    /// or a property setter or deleter).
+    pub fn is_property(&self) -> bool {
+        self.has_decorator(&["property", "cached_property", "setter", "getter", "deleter"])
+    }
+
+    /// Returns whether the member is a class method (`@classmethod`).
+    pub fn is_classmethod(&self) -> bool {
+        self.has_decorator(&["classmethod"])
+    }
+
+    /// Returns whether the member is a static method (`@staticmethod`).
+    pub fn is_staticmethod(&self) -> bool {
+        self.has_decorator(&["staticmethod"])
+    }
+
+    /// Returns whether the member is an abstract method (`@abstractmethod`, or one of the
+    /// deprecated `@abstractproperty`, `@abstractclassmethod` and `@abstractstaticmethod`).
+    pub fn is_abstract(&self) -> bool {
+        self.has_decorator(&[
+            "abstractmethod",
+            "abstractproperty",
+            "abstractclassmethod",
+            "abstractstaticmethod",
+        ])
+    }
+
+    /// Returns whether the member has a decorator whose last component is one of `names`.
+    fn has_decorator(&self, names: &[&str]) -> bool {
+        self.decorators
+            .iter()
+            .any(|decorator| names.contains(&decorator.rsplit('.').next().unwrap_or(decorator)))
+    }
+}
+
+/// The kind of a class member.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum MemberKind {
+    /// A method (`def` or `async def`)
+    Method,
+    /// A class-level attribute, assigned or only annotated
+    Attribute,
+}
+
+/// A decorator applied to a class.
+///
+/// E.g.
+/// `@dataclass` => { name=dataclass, args=[], keywords=[] }
+/// `@register_backend("s3")` => { name=register_backend, args=["s3"], keywords=[] }
+/// `@attrs.define(frozen=True)` => { name=attrs.define, args=[], keywords=[(frozen, True)] }
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct ClassDecorator {
+    /// The decorator as written, without any arguments (e.g. "register_backend")
+    pub name: String,
+    /// The positional arguments of the decorator call
+    ///
+    /// Arguments which aren't literals are kept as [`LiteralValue::Expr`], so that the
+    /// other arguments keep their positions.
+    pub args: Vec<LiteralValue>,
+    /// The keyword arguments of the decorator call, as (name, value) pairs
+    ///
+    /// Arguments which aren't literals are kept as [`LiteralValue::Expr`]. Unpacked
+    /// keyword arguments (`**kwargs`) are left out.
+    pub keywords: Vec<(String, LiteralValue)>,
+}
+
+impl fmt::Display for ClassDecorator {
+    /// Formats the decorator as Python, e.g. `@register_backend("s3", priority=10)`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "@{}", self.name)?;
+        if self.args.is_empty() && self.keywords.is_empty() {
+            return Ok(());
+        }
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(ToString::to_string)
+            .chain(
+                self.keywords
+                    .iter()
+                    .map(|(name, value)| format!("{name}={value}")),
+            )
+            .collect();
+        write!(f, "({})", args.join(", "))
+    }
+}
+
+/// A literal value, e.g. of a decorator argument.
+#[derive(Debug, Clone, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub enum LiteralValue {
+    /// A string, e.g. `"s3"`
+    Str(String),
+    /// A number as written, e.g. `10`, `-1.5` or `0x1F`
+    Number(String),
+    /// `True` or `False`
+    Bool(bool),
+    /// `None`
+    None,
+    /// A tuple of literals, e.g. `("a", 1)`
+    Tuple(Vec<LiteralValue>),
+    /// A list of literals, e.g. `["a", "b"]`
+    List(Vec<LiteralValue>),
+    /// An expression which isn't a literal, as written (e.g. `NAME`)
+    ///
+    /// This is only used for the arguments of decorators, in place of the arguments which
+    /// can't be evaluated.
+    Expr(String),
+}
+
+impl fmt::Display for LiteralValue {
+    /// Formats the value as Python (with strings in double quotes).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |values: &[LiteralValue]| {
+            values
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            LiteralValue::Str(value) => write!(f, "{value:?}"),
+            LiteralValue::Number(value) => f.write_str(value),
+            LiteralValue::Bool(true) => f.write_str("True"),
+            LiteralValue::Bool(false) => f.write_str("False"),
+            LiteralValue::None => f.write_str("None"),
+            LiteralValue::Tuple(values) if values.len() == 1 => write!(f, "({},)", values[0]),
+            LiteralValue::Tuple(values) => write!(f, "({})", join(values)),
+            LiteralValue::List(values) => write!(f, "[{}]", join(values)),
+            LiteralValue::Expr(source) => f.write_str(source),
+        }
+    }
 }
 
 /// An import.
@@ -36,6 +253,45 @@ pub struct Import {
     pub imported_as: String,
 }
 
+/// An import made inside a function, which is only visible to the code in that function.
+///
+/// E.g.
+/// `def make(): from a import b` => { scope=make.<locals>, import={ imported_item=a.b, imported_as=b } }
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct LocalImport {
+    /// The local scope the import is made in (e.g. "make.<locals>")
+    pub scope: String,
+    pub import: Import,
+}
+
+/// A module-level alias of a name or attribute.
+///
+/// E.g.
+/// `Base = Model` => { name=Base, target=Model }
+/// `Base = models.Model` => { name=Base, target=models.Model }
+/// `Base: TypeAlias = models.Model` => { name=Base, target=models.Model }
+/// `type Base = models.Model` => { name=Base, target=models.Model }
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct NameAlias {
+    pub name: String,
+    pub target: String,
+}
+
+/// A registration of a virtual subclass, e.g. with an abstract base class.
+///
+/// E.g.
+/// `Base.register(Foo)` => { base=Base, subclass=Foo }
+/// `abc.Sized.register(Foo)` => { base=abc.Sized, subclass=Foo }
+/// `@Base.register` on `class Foo` => { base=Base, subclass=Foo }
+#[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
+pub struct Registration {
+    /// The class registered with, as written
+    pub base: String,
+    /// The registered class, as written (or its qualified name in this module, for
+    /// the decorator form)
+    pub subclass: String,
+}
+
 /// The result of parsing a Python file.
 #[derive(Debug, Clone, bincode::Encode, bincode::Decode)]
 pub struct ParsedFile {
@@ -45,10 +301,37 @@ pub struct ParsedFile {
     pub module_path: String,
     /// Class definitions found in this file
     pub classes: Vec<ClassDefinition>,
-    /// Import statements found in this file (relative imports already resolved)
+    /// Module-level import statements found in this file (relative imports already resolved)
     pub imports: Vec<Import>,
-    /// Whether this is a package (__init__.py file)
+    /// Import statements found inside functions (relative imports already resolved)
+    pub local_imports: Vec<LocalImport>,
+    /// Whether this is a package (__init__.py or __init__.pyi file)
     pub is_package: bool,
+    /// The names listed in the module's `__all__`, if it is defined
+    pub all_names: Option<Vec<String>>,
+    /// Module-level aliases of names or attributes (e.g. `Base = models.Model`)
+    pub aliases: Vec<NameAlias>,
+    /// Virtual subclass registrations (e.g. `Base.register(Foo)`)
+    pub registrations: Vec<Registration>,
+    /// Whether parts of the file were skipped because of syntax errors
+    /// (see [`ParseOptions::error_tolerant`])
+    pub is_partial: bool,
+}
+
+/// Options controlling how Python files are parsed.
+#[derive(Debug, Clone, Default, PartialEq, Eq, bincode::Encode, bincode::Decode)]
+pub struct ParseOptions {
+    /// Additional names of calls whose positional arguments are forwarded as base classes,
+    /// e.g. `"make_base"` for `class Foo(make_base(Base, Mixin))`.
+    ///
+    /// A name matches a call if it is equal to the called name as written
+    /// (e.g. "compat.make_base"), or to its last component (e.g. "make_base").
+    pub base_forwarding_calls: Vec<String>,
+    /// Whether to salvage what can be parsed from files with syntax errors.
+    ///
+    /// If set, the top-level statements containing syntax errors are skipped, and the
+    /// file is marked as partially parsed. Otherwise a syntax error fails the whole file.
+    pub error_tolerant: bool,
 }
 
 /// Parses multiple Python files in parallel.
@@ -73,6 +356,23 @@ pub struct ParsedFile {
 /// infrastructure itself. Individual file parse errors are returned as `Err` variants
 /// within the result vector.
 pub fn parse_files(root_dir: &Path, python_files: &[PathBuf]) -> Result<Vec<Result<ParsedFile>>> {
+    parse_files_with_options(root_dir, python_files, &ParseOptions::default())
+}
+
+/// Parses multiple Python files in parallel, with custom parse options.
+///
+/// See [`parse_files`] for details.
+///
+/// # Arguments
+///
+/// * `root_dir` - The root directory of the Python project, used to compute module paths
+/// * `python_files` - Slice of paths to Python files to parse
+/// * `options` - Options controlling how the files are parsed
+pub fn parse_files_with_options(
+    root_dir: &Path,
+    python_files: &[PathBuf],
+    options: &ParseOptions,
+) -> Result<Vec<Result<ParsedFile>>> {
     Ok(python_files
         .par_iter()
         .map(|file_path| {
@@ -82,7 +382,7 @@ pub fn parse_files(root_dir: &Path, python_files: &[PathBuf]) -> Result<Vec<Resu
                     file: file_path.to_path_buf(),
                     error: "Failed to convert file path to module path".to_string(),
                 })?;
-            parse_file(file_path, &module_path)
+            parse_file_with_options(file_path, &module_path, options)
         })
         .collect())
 }
@@ -98,159 +398,863 @@ pub fn parse_files(root_dir: &Path, python_files: &[PathBuf]) -> Result<Vec<Resu
 ///
 /// A `ParsedFile` containing all classes and imports found in the file.
 pub fn parse_file(file_path: &Path, module_path: &str) -> Result<ParsedFile> {
-    let source = fs::read_to_string(file_path)?;
-
-    let parsed = parse_module(&source).map_err(|e| Error::ParseError {
-        file: file_path.to_path_buf(),
-        error: format!("{e:?}"),
-    })?;
-
-    let mut classes = Vec::new();
-    let mut imports = Vec::new();
-
-    extract_from_statements(
-        parsed.suite(),
-        None,
-        module_path,
-        file_path,
-        &mut classes,
-        &mut imports,
-    );
-
-    // Check if this is a package (__init__.py file)
-    let is_package = file_path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name == "__init__.py")
-        .unwrap_or(false);
-
-    Ok(ParsedFile {
-        file_path: file_path.to_path_buf(),
-        module_path: module_path.to_string(),
-        classes,
-        imports,
-        is_package,
-    })
+    parse_file_with_options(file_path, module_path, &ParseOptions::default())
 }
 
-/// Recursively extracts classes and imports from a list of statements.
+/// Parses a Python file with custom parse options.
 ///
-/// This function walks the AST and extracts:
-/// - Class definitions (including nested classes)
-/// - Import statements (both `import` and `from...import` forms)
+/// Jupyter notebooks (`.ipynb` files) are parsed from the source of their code cells
+/// (see [`notebook::extract_source`]).
 ///
-/// Nested classes are represented with dot notation (e.g., "Outer.Inner").
+/// The source is decoded according to its byte order mark or PEP 263 coding cookie
+/// (see [`encoding::read_source`]).
 ///
 /// # Arguments
 ///
-/// * `stmts` - The AST statements to process
-/// * `parent_class` - The parent class name if processing nested classes (e.g., `Some("Outer")`)
+/// * `file_path` - Path to the Python file
 /// * `module_path` - The module path for this file (e.g., "foo.bar")
-/// * `file_path` - The file path (used for resolving relative imports)
-/// * `classes` - Mutable vector to accumulate discovered class definitions
-/// * `imports` - Mutable vector to accumulate discovered imports (only at top level)
-fn extract_from_statements(
-    stmts: &[Stmt],
-    parent_class: Option<&str>,
-    module_path: &str,
+/// * `options` - Options controlling how the file is parsed
+///
+/// # Returns
+///
+/// A `ParsedFile` containing all classes and imports found in the file.
+pub fn parse_file_with_options(
     file_path: &Path,
-    classes: &mut Vec<ClassDefinition>,
-    imports: &mut Vec<Import>,
-) {
-    for stmt in stmts {
-        match stmt {
-            Stmt::ClassDef(class_def) => {
-                // Build fully qualified class name (e.g., "Outer.Inner" for nested classes)
-                let full_name = if let Some(parent) = parent_class {
-                    format!("{}.{}", parent, class_def.name)
-                } else {
-                    class_def.name.to_string()
-                };
-
-                // Extract base classes, filtering out unresolvable references
-                let bases = class_def
-                    .bases()
-                    .iter()
-                    .filter_map(extract_base_class)
-                    .collect();
-
-                classes.push(ClassDefinition {
-                    name: full_name.clone(),
-                    module_path: module_path.to_string(),
-                    file_path: file_path.to_path_buf(),
-                    bases,
-                });
-
-                // Recursively process nested classes
-                extract_from_statements(
-                    class_def.body.as_slice(),
-                    Some(&full_name),
-                    module_path,
-                    file_path,
-                    classes,
-                    imports,
-                );
+    module_path: &str,
+    options: &ParseOptions,
+) -> Result<ParsedFile> {
+    let mut source = encoding::read_source(file_path)?;
+
+    let notebook = if notebook::is_notebook_file(file_path) {
+        let notebook = notebook::extract_source(&source).map_err(|e| Error::ParseError {
+            file: file_path.to_path_buf(),
+            error: format!("Invalid notebook: {e}"),
+        })?;
+        source = notebook.source.clone();
+        Some(notebook)
+    } else {
+        None
+    };
+
+    let (parsed, is_partial) = match parse_module(&source) {
+        Ok(parsed) => (parsed, false),
+        Err(e) => {
+            let error = Error::ParseError {
+                file: file_path.to_path_buf(),
+                error: format!("{e:?}"),
+            };
+            if !options.error_tolerant {
+                return Err(error);
             }
-            Stmt::Import(import_stmt) => {
-                // Process `import foo` or `import foo as bar` statements
-                // Format: { imported_item: "foo", imported_as: "bar" }
-                for alias in &import_stmt.names {
-                    let imported_item = alias.name.to_string();
-                    let imported_as = alias
-                        .asname
-                        .as_ref()
-                        .map(|a| a.to_string())
-                        .unwrap_or_else(|| imported_item.clone());
-                    imports.push(Import {
-                        imported_item,
-                        imported_as,
-                    });
+
+            // Retry without the top-level chunks containing syntax errors
+            let Some(salvaged) = blank_unparseable_chunks(&source) else {
+                return Err(error);
+            };
+            let Ok(parsed) = parse_module(&salvaged) else {
+                return Err(error);
+            };
+            log::debug!(
+                "Partially parsed {} (skipped code with syntax errors)",
+                file_path.display()
+            );
+            source = salvaged;
+            (parsed, true)
+        }
+    };
+
+    let mut extractor = Extractor::new(&source, module_path, file_path, options);
+    extractor.extract_from_statements(parsed.suite(), None, &[]);
+
+    // Map locations in notebooks back to their cells
+    if let Some(notebook) = &notebook {
+        for class in &mut extractor.classes {
+            class.location = notebook.map_location(class.location);
+            for base in &mut class.bases {
+                base.location = notebook.map_location(base.location);
+            }
+        }
+    }
+
+    // Check if this is a package (__init__.py or __init__.pyi file)
+    let is_package = is_package_file(file_path);
+
+    Ok(ParsedFile {
+        file_path: file_path.to_path_buf(),
+        module_path: module_path.to_string(),
+        classes: extractor.classes,
+        imports: extractor.imports,
+        local_imports: extractor.local_imports,
+        is_package,
+        all_names: extractor.all_names,
+        aliases: extractor.aliases,
+        registrations: extractor.registrations,
+        is_partial,
+    })
+}
+
+/// Replaces the top-level chunks of a source which don't parse on their own with
+/// whitespace, so that the rest of the source can be parsed.
+///
+/// Line breaks and byte offsets are preserved, so locations in the rest of the source
+/// are unchanged. Returns `None` if every chunk has syntax errors.
+fn blank_unparseable_chunks(source: &str) -> Option<String> {
+    let mut salvaged = String::with_capacity(source.len());
+    let mut any_parsed = false;
+
+    for chunk in top_level_chunks(source) {
+        if parse_module(chunk).is_ok() {
+            any_parsed |= !chunk.trim().is_empty();
+            salvaged.push_str(chunk);
+        } else {
+            for c in chunk.chars() {
+                match c {
+                    '\n' | '\r' => salvaged.push(c),
+                    _ => salvaged.extend(std::iter::repeat_n(' ', c.len_utf8())),
                 }
             }
-            Stmt::ImportFrom(import_from) => {
-                // Process `from foo import bar` or `from .foo import bar` statements
-                let level = import_from.level as usize;
-
-                for alias in &import_from.names {
-                    let name = alias.name.to_string();
-                    let imported_as = alias
-                        .asname
-                        .as_ref()
-                        .map(|a| a.to_string())
-                        .unwrap_or_else(|| name.clone());
-
-                    let imported_item = if level > 0 {
-                        // Relative import: resolve dots to absolute module path
-                        // e.g., `from ..pkg import Foo` → "parent.pkg.Foo"
-                        let base_module = resolve_relative_module(module_path, level, file_path);
-                        if let Some(from_module) = import_from.module.as_ref() {
-                            format!("{base_module}.{from_module}.{name}")
-                        } else {
-                            format!("{base_module}.{name}")
+        }
+    }
+
+    any_parsed.then_some(salvaged)
+}
+
+/// Splits a source into chunks, each starting at a top-level statement.
+///
+/// A chunk starts at each unindented line, except for comments, closing brackets,
+/// continuations of compound statements (`else:`, `except:`, ...), and lines following
+/// a decorator or a backslash continuation. This is a heuristic: e.g. an unindented line
+/// in a multi-line string also starts a chunk.
+fn top_level_chunks(source: &str) -> Vec<&str> {
+    const CONTINUATIONS: [&str; 4] = ["else", "elif", "except", "finally"];
+
+    let mut chunks = Vec::new();
+    let mut chunk_start = 0;
+    let mut offset = 0;
+    let mut joins_next_line = false;
+
+    for line in source.split_inclusive('\n') {
+        let first_word = line
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or_default();
+        let starts_statement = line
+            .starts_with(|c: char| !c.is_whitespace() && !matches!(c, '#' | ')' | ']' | '}'))
+            && !CONTINUATIONS.contains(&first_word);
+
+        if starts_statement && !joins_next_line && offset > chunk_start {
+            chunks.push(&source[chunk_start..offset]);
+            chunk_start = offset;
+        }
+
+        let code = line.trim_end();
+        if !code.is_empty() && !code.trim_start().starts_with('#') {
+            joins_next_line = line.starts_with('@') || code.ends_with('\\');
+        }
+        offset += line.len();
+    }
+
+    if offset > chunk_start {
+        chunks.push(&source[chunk_start..offset]);
+    }
+    chunks
+}
+
+/// Walks the AST of a single file, accumulating classes and imports.
+struct Extractor<'a> {
+    /// The source text of the file (used to describe guard conditions)
+    source: &'a str,
+    /// The line index of the source text (used to compute locations)
+    line_index: LineIndex,
+    /// The module path for this file (e.g., "foo.bar")
+    module_path: &'a str,
+    /// The file path (used for resolving relative imports)
+    file_path: &'a Path,
+    /// Options controlling what is extracted
+    options: &'a ParseOptions,
+    /// Discovered class definitions
+    classes: Vec<ClassDefinition>,
+    /// Discovered module-level imports
+    imports: Vec<Import>,
+    /// Discovered imports made inside functions
+    local_imports: Vec<LocalImport>,
+    /// The names listed in `__all__`, if it is defined
+    all_names: Option<Vec<String>>,
+    /// Discovered module-level aliases
+    aliases: Vec<NameAlias>,
+    /// Discovered virtual subclass registrations
+    registrations: Vec<Registration>,
+}
+
+impl<'a> Extractor<'a> {
+    fn new(
+        source: &'a str,
+        module_path: &'a str,
+        file_path: &'a Path,
+        options: &'a ParseOptions,
+    ) -> Self {
+        Self {
+            source,
+            line_index: LineIndex::from_source_text(source),
+            module_path,
+            file_path,
+            options,
+            classes: Vec::new(),
+            imports: Vec::new(),
+            local_imports: Vec::new(),
+            all_names: None,
+            aliases: Vec::new(),
+            registrations: Vec::new(),
+        }
+    }
+
+    /// Recursively extracts classes and imports from a list of statements.
+    ///
+    /// This function walks the AST and extracts:
+    /// - Class definitions (including nested classes)
+    /// - Import statements (both `import` and `from...import` forms)
+    /// - The module's `__all__` list (literal lists/tuples, and `+=` extensions)
+    /// - Module-level aliases (e.g. `Base = models.Model` or `type Base = models.Model`)
+    /// - Dynamically created classes (e.g. `Foo = type("Foo", (Base,), {})`)
+    /// - Virtual subclass registrations (e.g. `Base.register(Foo)` or `@Base.register`)
+    ///
+    /// Nested classes are represented with dot notation (e.g., "Outer.Inner"), and
+    /// classes defined inside functions or methods use CPython-style qualified names
+    /// (e.g., "make_handler.<locals>.Handler" or "Outer.method.<locals>.Handler").
+    /// Compound statements (`if`, `try`, `with`, `for`, `while`, `match`) are
+    /// descended into, and the guard of each enclosing block is recorded on the
+    /// classes defined inside it.
+    ///
+    /// # Arguments
+    ///
+    /// * `stmts` - The AST statements to process
+    /// * `scope` - The qualified name of the enclosing class or function scope, if any
+    ///   (e.g., `Some("Outer")` or `Some("make_handler.<locals>")`)
+    /// * `guards` - Descriptions of the enclosing guards, outermost first (e.g., `["if TYPE_CHECKING"]`)
+    fn extract_from_statements(&mut self, stmts: &[Stmt], scope: Option<&str>, guards: &[String]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::ClassDef(class_def) => {
+                    // Build fully qualified class name (e.g., "Outer.Inner" for nested classes)
+                    let full_name = if let Some(scope) = scope {
+                        format!("{}.{}", scope, class_def.name)
+                    } else {
+                        class_def.name.to_string()
+                    };
+
+                    // Extract base classes, filtering out unresolvable references
+                    let bases = class_def
+                        .bases()
+                        .iter()
+                        .flat_map(|base| self.extract_base_classes(base))
+                        .collect();
+
+                    self.classes.push(ClassDefinition {
+                        name: full_name.clone(),
+                        module_path: self.module_path.to_string(),
+                        file_path: self.file_path.to_path_buf(),
+                        bases,
+                        guard: (!guards.is_empty()).then(|| guards.join(", ")),
+                        is_dynamic: false,
+                        metaclass: extract_metaclass(class_def),
+                        location: self.location(class_def.start(), class_def.end()),
+                        members: self.extract_members(&class_def.body),
+                        decorators: class_def
+                            .decorator_list
+                            .iter()
+                            .filter_map(|decorator| self.extract_class_decorator(decorator))
+                            .collect(),
+                    });
+
+                    // `@Base.register`
+                    for decorator in &class_def.decorator_list {
+                        if let Expr::Attribute(attribute) = &decorator.expression
+                            && attribute.attr.as_str() == "register"
+                            && let Some(base) = extract_base_class(&attribute.value)
+                        {
+                            self.registrations.push(Registration {
+                                base,
+                                subclass: full_name.clone(),
+                            });
                         }
+                    }
+
+                    // Recursively process nested classes
+                    self.extract_from_statements(
+                        class_def.body.as_slice(),
+                        Some(&full_name),
+                        guards,
+                    );
+                }
+                Stmt::FunctionDef(function_def) => {
+                    // Classes defined inside functions are local classes
+                    // (e.g., "make_handler.<locals>.Handler")
+                    let local_scope = if let Some(scope) = scope {
+                        format!("{}.{}.<locals>", scope, function_def.name)
                     } else {
-                        // Absolute import: combine module and name
-                        // e.g., `from foo import Bar` → "foo.Bar"
-                        let from_module = import_from
-                            .module
+                        format!("{}.<locals>", function_def.name)
+                    };
+                    self.extract_from_statements(&function_def.body, Some(&local_scope), guards);
+                }
+                Stmt::If(if_stmt) => {
+                    let guard = format!("if {}", self.source_text(if_stmt.test.as_ref()));
+                    self.extract_guarded(&if_stmt.body, scope, guards, guard);
+                    for clause in &if_stmt.elif_else_clauses {
+                        let guard = match &clause.test {
+                            Some(test) => format!("elif {}", self.source_text(test)),
+                            None => "else".to_string(),
+                        };
+                        self.extract_guarded(&clause.body, scope, guards, guard);
+                    }
+                }
+                Stmt::Try(try_stmt) => {
+                    self.extract_guarded(&try_stmt.body, scope, guards, "try".to_string());
+                    for handler in &try_stmt.handlers {
+                        let ExceptHandler::ExceptHandler(handler) = handler;
+                        let guard = match &handler.type_ {
+                            Some(type_) => format!("except {}", self.source_text(type_.as_ref())),
+                            None => "except".to_string(),
+                        };
+                        self.extract_guarded(&handler.body, scope, guards, guard);
+                    }
+                    self.extract_guarded(&try_stmt.orelse, scope, guards, "try-else".to_string());
+                    // `finally` always runs, so it doesn't add a guard
+                    self.extract_from_statements(&try_stmt.finalbody, scope, guards);
+                }
+                Stmt::With(with_stmt) => {
+                    let items = match (with_stmt.items.first(), with_stmt.items.last()) {
+                        (Some(first), Some(last)) => self.source_slice(
+                            first.range.start().to_usize(),
+                            last.range.end().to_usize(),
+                        ),
+                        _ => String::new(),
+                    };
+                    let guard = format!("with {items}");
+                    self.extract_guarded(&with_stmt.body, scope, guards, guard);
+                }
+                Stmt::For(for_stmt) => {
+                    let guard = format!(
+                        "for {} in {}",
+                        self.source_text(for_stmt.target.as_ref()),
+                        self.source_text(for_stmt.iter.as_ref())
+                    );
+                    self.extract_guarded(&for_stmt.body, scope, guards, guard);
+                    self.extract_guarded(&for_stmt.orelse, scope, guards, "for-else".to_string());
+                }
+                Stmt::While(while_stmt) => {
+                    let guard = format!("while {}", self.source_text(while_stmt.test.as_ref()));
+                    self.extract_guarded(&while_stmt.body, scope, guards, guard);
+                    self.extract_guarded(
+                        &while_stmt.orelse,
+                        scope,
+                        guards,
+                        "while-else".to_string(),
+                    );
+                }
+                Stmt::Match(match_stmt) => {
+                    for case in &match_stmt.cases {
+                        let guard = format!("case {}", self.source_text(&case.pattern));
+                        self.extract_guarded(&case.body, scope, guards, guard);
+                    }
+                }
+                Stmt::Import(import_stmt) => {
+                    // Process `import foo` or `import foo as bar` statements
+                    // Format: { imported_item: "foo", imported_as: "bar" }
+                    for alias in &import_stmt.names {
+                        let imported_item = alias.name.to_string();
+                        let imported_as = alias
+                            .asname
                             .as_ref()
-                            .map(|m| m.to_string())
-                            .unwrap_or_default();
-                        if from_module.is_empty() {
-                            name.clone()
+                            .map(|a| a.to_string())
+                            .unwrap_or_else(|| imported_item.clone());
+                        self.add_import(
+                            scope,
+                            Import {
+                                imported_item,
+                                imported_as,
+                            },
+                        );
+                    }
+                }
+                Stmt::ImportFrom(import_from) => {
+                    // Process `from foo import bar` or `from .foo import bar` statements
+                    let level = import_from.level as usize;
+
+                    for alias in &import_from.names {
+                        let name = alias.name.to_string();
+                        let imported_as = alias
+                            .asname
+                            .as_ref()
+                            .map(|a| a.to_string())
+                            .unwrap_or_else(|| name.clone());
+
+                        let imported_item = if level > 0 {
+                            // Relative import: resolve dots to absolute module path
+                            // e.g., `from ..pkg import Foo` → "parent.pkg.Foo"
+                            let base_module =
+                                resolve_relative_module(self.module_path, level, self.file_path);
+                            if let Some(from_module) = import_from.module.as_ref() {
+                                format!("{base_module}.{from_module}.{name}")
+                            } else {
+                                format!("{base_module}.{name}")
+                            }
                         } else {
-                            format!("{from_module}.{name}")
-                        }
-                    };
+                            // Absolute import: combine module and name
+                            // e.g., `from foo import Bar` → "foo.Bar"
+                            let from_module = import_from
+                                .module
+                                .as_ref()
+                                .map(|m| m.to_string())
+                                .unwrap_or_default();
+                            if from_module.is_empty() {
+                                name.clone()
+                            } else {
+                                format!("{from_module}.{name}")
+                            }
+                        };
+
+                        self.add_import(
+                            scope,
+                            Import {
+                                imported_item,
+                                imported_as,
+                            },
+                        );
+                    }
+                }
+                Stmt::Assign(assign) if scope.is_none() => {
+                    // `__all__ = ["Foo", "Bar"]`
+                    if assign.targets.iter().any(is_dunder_all)
+                        && let Some(names) = extract_string_list(&assign.value)
+                    {
+                        self.all_names = Some(names);
+                    }
 
-                    imports.push(Import {
-                        imported_item,
-                        imported_as,
-                    });
+                    // `Base = models.Model` (or `A = B = models.Model`)
+                    for target in &assign.targets {
+                        self.extract_alias(target, &assign.value);
+                    }
+
+                    // `Foo = type("Foo", (Base,), {})`
+                    for target in &assign.targets {
+                        self.extract_dynamic_class(target, &assign.value, guards);
+                    }
+                }
+                Stmt::AnnAssign(ann_assign) if scope.is_none() => {
+                    // `__all__: list[str] = ["Foo", "Bar"]`
+                    if is_dunder_all(&ann_assign.target)
+                        && let Some(value) = &ann_assign.value
+                        && let Some(names) = extract_string_list(value)
+                    {
+                        self.all_names = Some(names);
+                    }
+
+                    // `Base: TypeAlias = models.Model`
+                    if let Some(value) = &ann_assign.value {
+                        self.extract_alias(&ann_assign.target, value);
+                        self.extract_dynamic_class(&ann_assign.target, value, guards);
+                    }
+                }
+                Stmt::TypeAlias(type_alias) if scope.is_none() => {
+                    // `type Base = models.Model`
+                    self.extract_alias(&type_alias.name, &type_alias.value);
+                }
+                Stmt::Expr(expr_stmt) => {
+                    // `Base.register(Foo)`
+                    if let Expr::Call(call) = expr_stmt.value.as_ref()
+                        && let Expr::Attribute(attribute) = call.func.as_ref()
+                        && attribute.attr.as_str() == "register"
+                        && let [subclass] = &call.arguments.args[..]
+                        && let Some(base) = extract_base_class(&attribute.value)
+                        && let Some(subclass) = extract_base_class(subclass)
+                    {
+                        self.registrations.push(Registration { base, subclass });
+                    }
+                }
+                Stmt::AugAssign(aug_assign) if scope.is_none() => {
+                    // `__all__ += ["Baz"]`
+                    if matches!(aug_assign.op, Operator::Add)
+                        && is_dunder_all(&aug_assign.target)
+                        && let Some(names) = extract_string_list(&aug_assign.value)
+                    {
+                        self.all_names.get_or_insert_with(Vec::new).extend(names);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Records an import made in `scope`.
+    ///
+    /// Imports made inside a function (or in a class defined inside a function) only bind
+    /// names in that function, so they are recorded as local imports of the innermost
+    /// enclosing function scope. Other imports are module-level imports.
+    fn add_import(&mut self, scope: Option<&str>, import: Import) {
+        const LOCALS: &str = ".<locals>";
+
+        match scope.and_then(|scope| scope.rfind(LOCALS).map(|i| &scope[..i + LOCALS.len()])) {
+            Some(local_scope) => self.local_imports.push(LocalImport {
+                scope: local_scope.to_string(),
+                import,
+            }),
+            None => self.imports.push(import),
+        }
+    }
+
+    /// Records `target = value` as an alias if the target is a plain name and the
+    /// value is a name or attribute (e.g. `Base = models.Model`).
+    fn extract_alias(&mut self, target: &Expr, value: &Expr) {
+        if let Expr::Name(name) = target
+            && !is_dunder_all(target)
+            && let Some(alias_target) = extract_base_class(value)
+        {
+            self.aliases.push(NameAlias {
+                name: name.id.to_string(),
+                target: alias_target,
+            });
+        }
+    }
+
+    /// Records `target = value` as a class definition if the target is a plain name and
+    /// the value creates a class dynamically with a literal tuple of bases, i.e.
+    /// `type("Foo", (Base, Mixin), {...})` or `types.new_class("Foo", (Base,))`.
+    ///
+    /// `NewType("UserId", Base)` is also recorded, as a class with the single base `Base`.
+    fn extract_dynamic_class(&mut self, target: &Expr, value: &Expr, guards: &[String]) {
+        let (Expr::Name(name), Expr::Call(call)) = (target, value) else {
+            return;
+        };
+        let args = &call.arguments.args;
+
+        let bases = match extract_base_class(&call.func).as_deref() {
+            // `type(name, bases, dict)` - the one-argument form returns the type of an object
+            Some("type" | "builtins.type") if args.len() == 3 => match args.get(1) {
+                Some(Expr::Tuple(tuple)) => self.extract_tuple_bases(&tuple.elts),
+                // Bases that aren't a literal tuple can't be resolved statically
+                _ => return,
+            },
+            Some(name @ ("new_class" | "types.new_class"))
+                if name == "types.new_class" || self.is_imported_as("types.new_class", name) =>
+            {
+                let bases_arg = args
+                    .get(1)
+                    .or_else(|| call.arguments.find_keyword("bases").map(|k| &k.value));
+                match bases_arg {
+                    Some(Expr::Tuple(tuple)) => self.extract_tuple_bases(&tuple.elts),
+                    // `types.new_class("Foo")` creates a class with no bases
+                    None => Vec::new(),
+                    Some(_) => return,
+                }
+            }
+            // `NewType("UserId", Base)` - treated as a subclass of its supertype
+            Some("NewType" | "typing.NewType" | "typing_extensions.NewType") => match args.get(1) {
+                Some(supertype) => self.extract_base_classes(supertype),
+                None => return,
+            },
+            _ => return,
+        };
+
+        self.classes.push(ClassDefinition {
+            name: name.id.to_string(),
+            module_path: self.module_path.to_string(),
+            file_path: self.file_path.to_path_buf(),
+            bases,
+            guard: (!guards.is_empty()).then(|| guards.join(", ")),
+            is_dynamic: true,
+            metaclass: None,
+            location: self.location(target.start(), value.end()),
+            members: Vec::new(),
+            decorators: Vec::new(),
+        });
+    }
+
+    /// Returns whether `name` is bound by a module-level import of `item` so far
+    /// (e.g. "new_class" after `from types import new_class`).
+    fn is_imported_as(&self, item: &str, name: &str) -> bool {
+        self.imports
+            .iter()
+            .any(|import| import.imported_item == item && import.imported_as == name)
+    }
+
+    /// Extracts the base classes from the elements of a literal tuple of bases.
+    fn extract_tuple_bases(&self, elements: &[Expr]) -> Vec<BaseClass> {
+        elements
+            .iter()
+            .flat_map(|base| self.extract_base_classes(base))
+            .collect()
+    }
+
+    /// Extracts the base classes from a base class expression.
+    ///
+    /// Usually this is a single class reference (see [`extract_base_class`]), but calls
+    /// to helpers which forward their arguments as bases are looked through:
+    /// - `with_metaclass(Meta, Base1, Base2)` (e.g. from `six` or `future.utils`)
+    ///   forwards all but its first argument
+    /// - Calls configured in [`ParseOptions::base_forwarding_calls`] forward all of their
+    ///   positional arguments
+    fn extract_base_classes(&self, expr: &Expr) -> Vec<BaseClass> {
+        let Expr::Call(call) = expr else {
+            return self.extract_base(expr).into_iter().collect();
+        };
+        let Some(call_name) = extract_base_class(&call.func) else {
+            return Vec::new();
+        };
+
+        let last_component = call_name.rsplit('.').next().unwrap_or(&call_name);
+        let forwarded_args = if last_component == "with_metaclass" {
+            call.arguments.args.get(1..).unwrap_or_default()
+        } else if self
+            .options
+            .base_forwarding_calls
+            .iter()
+            .any(|name| *name == call_name || name == last_component)
+        {
+            &call.arguments.args[..]
+        } else {
+            return Vec::new();
+        };
+
+        forwarded_args
+            .iter()
+            .flat_map(|arg| self.extract_base_classes(arg))
+            .collect()
+    }
+
+    /// Extracts a single base class reference, keeping any subscript as type arguments.
+    fn extract_base(&self, expr: &Expr) -> Option<BaseClass> {
+        let name = extract_base_class(expr)?;
+        let type_args = match expr {
+            Expr::Subscript(subscript) => match subscript.slice.as_ref() {
+                Expr::Tuple(tuple) => tuple
+                    .elts
+                    .iter()
+                    .map(|arg| self.extract_type_arg(arg))
+                    .collect(),
+                arg => vec![self.extract_type_arg(arg)],
+            },
+            _ => Vec::new(),
+        };
+        Some(BaseClass {
+            name,
+            type_args,
+            location: self.location(expr.start(), expr.end()),
+        })
+    }
+
+    /// Extracts a type argument of a subscripted base class (see [`BaseClass::type_args`]).
+    fn extract_type_arg(&self, expr: &Expr) -> String {
+        match expr {
+            Expr::StringLiteral(string) => string.value.to_str().to_string(),
+            _ => extract_base_class(expr)
+                .filter(|_| !matches!(expr, Expr::Subscript(_)))
+                .unwrap_or_else(|| self.source_text(expr)),
+        }
+    }
+
+    /// Extracts the members defined in a class body.
+    ///
+    /// Members defined in the branches of `if` and `try` statements are included. Each
+    /// name is recorded once, at the position of its first definition. As the last
+    /// assignment wins at runtime, a later unconditional definition (directly in the class
+    /// body) replaces an earlier one, while conditional redefinitions are ignored.
+    fn extract_members(&self, body: &[Stmt]) -> Vec<ClassMember> {
+        let mut members = Vec::new();
+        self.collect_members(body, true, &mut members);
+        members
+    }
+
+    /// Adds the members defined by statements in a class body to `members`.
+    ///
+    /// `unconditional` is whether the statements always run when the class is defined.
+    fn collect_members(&self, stmts: &[Stmt], unconditional: bool, members: &mut Vec<ClassMember>) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::FunctionDef(function_def) => {
+                    let decorators = function_def
+                        .decorator_list
+                        .iter()
+                        .filter_map(decorator_name)
+                        .collect();
+                    add_member(
+                        members,
+                        function_def.name.as_str(),
+                        MemberKind::Method,
+                        decorators,
+                        None,
+                        None,
+                        unconditional,
+                    );
+                }
+                Stmt::Assign(assign) => {
+                    for target in &assign.targets {
+                        // Only a single name is assigned the value as a whole
+                        let value = match target {
+                            Expr::Name(_) => self.extract_literal(&assign.value),
+                            _ => None,
+                        };
+                        for name in target_names(target) {
+                            add_member(
+                                members,
+                                name,
+                                MemberKind::Attribute,
+                                Vec::new(),
+                                value.clone(),
+                                None,
+                                unconditional,
+                            );
+                        }
+                    }
+                }
+                Stmt::AnnAssign(ann_assign) => {
+                    if let Expr::Name(name) = ann_assign.target.as_ref() {
+                        let value = ann_assign
+                            .value
+                            .as_ref()
+                            .and_then(|value| self.extract_literal(value));
+                        add_member(
+                            members,
+                            name.id.as_str(),
+                            MemberKind::Attribute,
+                            Vec::new(),
+                            value,
+                            Some(self.source_text(ann_assign.annotation.as_ref())),
+                            // An annotation without a value doesn't assign the attribute
+                            unconditional && ann_assign.value.is_some(),
+                        );
+                    }
+                }
+                Stmt::If(if_stmt) => {
+                    self.collect_members(&if_stmt.body, false, members);
+                    for clause in &if_stmt.elif_else_clauses {
+                        self.collect_members(&clause.body, false, members);
+                    }
+                }
+                Stmt::Try(try_stmt) => {
+                    self.collect_members(&try_stmt.body, false, members);
+                    for ExceptHandler::ExceptHandler(handler) in &try_stmt.handlers {
+                        self.collect_members(&handler.body, false, members);
+                    }
+                    self.collect_members(&try_stmt.orelse, false, members);
+                    // `finally` always runs
+                    self.collect_members(&try_stmt.finalbody, unconditional, members);
                 }
+                _ => {}
+            }
+        }
+    }
+
+    /// Extracts a decorator applied to a class, with its arguments.
+    fn extract_class_decorator(&self, decorator: &Decorator) -> Option<ClassDecorator> {
+        let Expr::Call(call) = &decorator.expression else {
+            return Some(ClassDecorator {
+                name: extract_base_class(&decorator.expression)?,
+                args: Vec::new(),
+                keywords: Vec::new(),
+            });
+        };
+        Some(ClassDecorator {
+            name: extract_base_class(&call.func)?,
+            args: call
+                .arguments
+                .args
+                .iter()
+                .map(|arg| self.extract_argument(arg))
+                .collect(),
+            keywords: call
+                .arguments
+                .keywords
+                .iter()
+                .filter_map(|keyword| {
+                    Some((
+                        keyword.arg.as_ref()?.to_string(),
+                        self.extract_argument(&keyword.value),
+                    ))
+                })
+                .collect(),
+        })
+    }
+
+    /// Extracts the value of a call argument: a literal value, or the expression as
+    /// written if it isn't a literal.
+    fn extract_argument(&self, expr: &Expr) -> LiteralValue {
+        self.extract_literal(expr)
+            .unwrap_or_else(|| LiteralValue::Expr(self.source_text(expr)))
+    }
+
+    /// Extracts a literal value, or `None` if the expression isn't a literal.
+    fn extract_literal(&self, expr: &Expr) -> Option<LiteralValue> {
+        let extract_all = |elements: &[Expr]| {
+            elements
+                .iter()
+                .map(|element| self.extract_literal(element))
+                .collect::<Option<Vec<_>>>()
+        };
+        match expr {
+            Expr::StringLiteral(string) => {
+                Some(LiteralValue::Str(string.value.to_str().to_string()))
             }
-            _ => {}
+            Expr::NumberLiteral(_) => Some(LiteralValue::Number(self.source_text(expr))),
+            // Negative numbers, e.g. `-1`
+            Expr::UnaryOp(unary)
+                if matches!(unary.op, UnaryOp::USub | UnaryOp::UAdd)
+                    && matches!(unary.operand.as_ref(), Expr::NumberLiteral(_)) =>
+            {
+                Some(LiteralValue::Number(
+                    self.source_text(expr).replace(' ', ""),
+                ))
+            }
+            Expr::BooleanLiteral(boolean) => Some(LiteralValue::Bool(boolean.value)),
+            Expr::NoneLiteral(_) => Some(LiteralValue::None),
+            Expr::Tuple(tuple) => extract_all(&tuple.elts).map(LiteralValue::Tuple),
+            Expr::List(list) => extract_all(&list.elts).map(LiteralValue::List),
+            _ => None,
+        }
+    }
+
+    /// Extracts from the body of a compound statement, adding `guard` to the enclosing guards.
+    fn extract_guarded(
+        &mut self,
+        stmts: &[Stmt],
+        scope: Option<&str>,
+        guards: &[String],
+        guard: String,
+    ) {
+        if stmts.is_empty() {
+            return;
         }
+        let mut guards = guards.to_vec();
+        guards.push(guard);
+        self.extract_from_statements(stmts, scope, &guards);
+    }
+
+    /// Returns the location of the source range between two offsets.
+    fn location(&self, start: TextSize, end: TextSize) -> Location {
+        let start = self.line_index.source_location(start, self.source);
+        let end = self.line_index.source_location(end, self.source);
+        Location {
+            line: start.row.get() as u32,
+            column: start.column.get() as u32,
+            end_line: end.row.get() as u32,
+            end_column: end.column.get() as u32,
+            cell: None,
+        }
+    }
+
+    /// Returns the source text of a node, with whitespace collapsed onto one line.
+    fn source_text(&self, node: &impl Ranged) -> String {
+        let range = node.range();
+        self.source_slice(range.start().to_usize(), range.end().to_usize())
+    }
+
+    /// Returns a slice of the source text, with whitespace collapsed onto one line.
+    fn source_slice(&self, start: usize, end: usize) -> String {
+        self.source
+            .get(start..end)
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 }
 
@@ -285,11 +1289,7 @@ fn extract_from_statements(
 /// from .local import Bar   # level=1 → resolves to "pkg.sub.local"
 /// ```
 fn resolve_relative_module(current_module: &str, level: usize, file_path: &Path) -> String {
-    let is_package = file_path
-        .file_name()
-        .and_then(|name| name.to_str())
-        .map(|name| name == "__init__.py")
-        .unwrap_or(false);
+    let is_package = is_package_file(file_path);
 
     let parts: Vec<&str> = current_module.split('.').collect();
 
@@ -307,6 +1307,22 @@ fn resolve_relative_module(current_module: &str, level: usize, file_path: &Path)
     }
 }
 
+/// Returns whether a file is a package's `__init__.py` (or `__init__.pyi` stub).
+fn is_package_file(file_path: &Path) -> bool {
+    file_path
+        .file_stem()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name == "__init__")
+}
+
+/// Returns whether a file is a Python stub file (`.pyi`).
+pub fn is_stub_file(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext == "pyi")
+}
+
 /// Extracts a base class reference from an expression as a string.
 /// Returns strings like "Foo" or "module.Foo" or "pkg.mod.Foo"
 fn extract_base_class(expr: &Expr) -> Option<String> {
@@ -346,6 +1362,131 @@ fn extract_base_class(expr: &Expr) -> Option<String> {
     }
 }
 
+/// Extracts the metaclass declared by a class definition, if any.
+///
+/// Recognises:
+/// - The `metaclass` keyword: `class Foo(Base, metaclass=Meta)`
+/// - `with_metaclass` bases: `class Foo(six.with_metaclass(Meta, Base))`
+/// - `add_metaclass` decorators: `@six.add_metaclass(Meta)`
+fn extract_metaclass(class_def: &StmtClassDef) -> Option<String> {
+    if let Some(keyword) = class_def.keywords().iter().find(|keyword| {
+        keyword
+            .arg
+            .as_ref()
+            .is_some_and(|arg| arg.as_str() == "metaclass")
+    }) {
+        return extract_base_class(&keyword.value);
+    }
+
+    // Returns the first argument of a call to a function with the given name
+    let first_arg_of = |expr: &Expr, function_name: &str| match expr {
+        Expr::Call(call)
+            if extract_base_class(&call.func)
+                .is_some_and(|name| name.rsplit('.').next() == Some(function_name)) =>
+        {
+            call.arguments.args.first().and_then(extract_base_class)
+        }
+        _ => None,
+    };
+
+    class_def
+        .bases()
+        .iter()
+        .find_map(|base| first_arg_of(base, "with_metaclass"))
+        .or_else(|| {
+            class_def
+                .decorator_list
+                .iter()
+                .find_map(|decorator| first_arg_of(&decorator.expression, "add_metaclass"))
+        })
+}
+
+/// Records a class member.
+///
+/// If a member with the same name was already recorded, it is replaced by an
+/// unconditional definition (keeping its position), and kept otherwise. Either way, an
+/// annotation is kept unless the new definition is annotated too.
+fn add_member(
+    members: &mut Vec<ClassMember>,
+    name: &str,
+    kind: MemberKind,
+    decorators: Vec<String>,
+    value: Option<LiteralValue>,
+    annotation: Option<String>,
+    unconditional: bool,
+) {
+    match members.iter_mut().find(|member| member.name == name) {
+        Some(existing) => {
+            if unconditional {
+                existing.kind = kind;
+                existing.decorators = decorators;
+                existing.value = value;
+            }
+            if annotation.is_some() && (unconditional || existing.annotation.is_none()) {
+                existing.annotation = annotation;
+            }
+        }
+        None => members.push(ClassMember {
+            name: name.to_string(),
+            kind,
+            decorators,
+            value,
+            annotation,
+        }),
+    }
+}
+
+/// Returns the name of a decorator, without any arguments
+/// (e.g. "functools.lru_cache" for `@functools.lru_cache(maxsize=None)`).
+fn decorator_name(decorator: &Decorator) -> Option<String> {
+    match &decorator.expression {
+        Expr::Call(call) => extract_base_class(&call.func),
+        expression => extract_base_class(expression),
+    }
+}
+
+/// Returns the names bound by an assignment target, e.g. `a` or `a, b`.
+fn target_names(target: &Expr) -> Vec<&str> {
+    match target {
+        Expr::Name(name) => vec![name.id.as_str()],
+        Expr::Tuple(tuple) => tuple.elts.iter().flat_map(target_names).collect(),
+        Expr::List(list) => list.elts.iter().flat_map(target_names).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Returns whether an assignment target is the name `__all__`.
+fn is_dunder_all(target: &Expr) -> bool {
+    matches!(target, Expr::Name(name) if name.id.as_str() == "__all__")
+}
+
+/// Extracts the string literals of a literal list or tuple, e.g. `["Foo", "Bar"]`.
+///
+/// Concatenations of literal lists (e.g. `["Foo"] + ["Bar"]`) are supported. Returns
+/// `None` if the expression is not made up of literal lists/tuples.
+fn extract_string_list(expr: &Expr) -> Option<Vec<String>> {
+    let elements = match expr {
+        Expr::List(list) => &list.elts,
+        Expr::Tuple(tuple) => &tuple.elts,
+        Expr::BinOp(bin_op) if matches!(bin_op.op, Operator::Add) => {
+            let mut names = extract_string_list(&bin_op.left)?;
+            names.extend(extract_string_list(&bin_op.right)?);
+            return Some(names);
+        }
+        _ => return None,
+    };
+
+    Some(
+        elements
+            .iter()
+            .filter_map(|element| match element {
+                Expr::StringLiteral(string) => Some(string.value.to_str().to_string()),
+                _ => None,
+            })
+            .collect(),
+    )
+}
+
 /// Converts a file path to a Python module path.
 ///
 /// # Arguments
@@ -361,7 +1502,7 @@ pub fn file_path_to_module_path(file_path: &Path, root_dir: &Path) -> Option<Str
     // Get the relative path from root_dir to file_path
     let rel_path = file_path.strip_prefix(root_dir).ok()?;
 
-    // Remove the .py extension
+    // Remove the .py (or .pyi) extension
     let without_ext = rel_path.with_extension("");
 
     // Convert path components to module path
@@ -406,57 +1547,1146 @@ mod tests {
         let path = Path::new("/project/src/foo/bar/__init__.py");
         let root = Path::new("/project/src");
         assert_eq!(
-            file_path_to_module_path(path, root),
-            Some("foo.bar".to_string())
+            file_path_to_module_path(path, root),
+            Some("foo.bar".to_string())
+        );
+
+        // Top-level module
+        let path = Path::new("/project/src/module.py");
+        let root = Path::new("/project/src");
+        assert_eq!(
+            file_path_to_module_path(path, root),
+            Some("module".to_string())
+        );
+
+        // Stub files map to the same module as their source
+        let path = Path::new("/project/src/foo/bar/baz.pyi");
+        let root = Path::new("/project/src");
+        assert_eq!(
+            file_path_to_module_path(path, root),
+            Some("foo.bar.baz".to_string())
+        );
+        let path = Path::new("/project/src/foo/bar/__init__.pyi");
+        let root = Path::new("/project/src");
+        assert_eq!(
+            file_path_to_module_path(path, root),
+            Some("foo.bar".to_string())
+        );
+
+        // Top-level __init__.py (edge case)
+        let path = Path::new("/project/src/__init__.py");
+        let root = Path::new("/project/src");
+        assert_eq!(file_path_to_module_path(path, root), None);
+    }
+
+    #[test]
+    fn test_file_path_to_module_path_relative() {
+        // Test with matching prefixes
+        let path = Path::new("./foo/bar.py");
+        let root = Path::new(".");
+        assert_eq!(
+            file_path_to_module_path(path, root),
+            Some("foo.bar".to_string())
+        );
+    }
+
+    #[test]
+    fn test_nested_class_extraction() {
+        // Create a temporary Python file with nested classes
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_nested_classes.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+class Foo:
+    pass
+
+class Bar:
+    class NestedInBar(Foo):
+        pass
+
+    class AnotherNested:
+        class DoublyNested(Foo):
+            pass
+
+class TopLevel(Foo):
+    pass
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        // Check that we found all classes with correct names
+        let class_names: Vec<&str> = parsed.classes.iter().map(|c| c.name.as_str()).collect();
+
+        assert_eq!(class_names.len(), 6);
+        assert!(class_names.contains(&"Foo"));
+        assert!(class_names.contains(&"Bar"));
+        assert!(class_names.contains(&"Bar.NestedInBar"));
+        assert!(class_names.contains(&"Bar.AnotherNested"));
+        assert!(class_names.contains(&"Bar.AnotherNested.DoublyNested"));
+        assert!(class_names.contains(&"TopLevel"));
+
+        // Verify that NestedInBar has Foo as a base
+        let nested_in_bar = parsed
+            .classes
+            .iter()
+            .find(|c| c.name == "Bar.NestedInBar")
+            .unwrap();
+        assert_eq!(nested_in_bar.bases.len(), 1);
+        assert_eq!(nested_in_bar.bases[0].name, "Foo");
+
+        // Verify that DoublyNested has Foo as a base
+        let doubly_nested = parsed
+            .classes
+            .iter()
+            .find(|c| c.name == "Bar.AnotherNested.DoublyNested")
+            .unwrap();
+        assert_eq!(doubly_nested.bases.len(), 1);
+        assert_eq!(doubly_nested.bases[0].name, "Foo");
+    }
+
+    #[test]
+    fn test_local_class_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_local_classes.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+class Base:
+    pass
+
+def make_handler():
+    class Handler(Base):
+        class Nested:
+            pass
+    return Handler
+
+class Factory:
+    def build(self):
+        def inner():
+            from products import Base
+            class Product(Base):
+                pass
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let class_names: Vec<&str> = parsed.classes.iter().map(|c| c.name.as_str()).collect();
+
+        assert_eq!(
+            class_names,
+            vec![
+                "Base",
+                "make_handler.<locals>.Handler",
+                "make_handler.<locals>.Handler.Nested",
+                "Factory",
+                "Factory.build.<locals>.inner.<locals>.Product",
+            ]
+        );
+
+        // Imports inside functions are only visible in the function
+        assert!(parsed.imports.is_empty());
+        assert_eq!(parsed.local_imports.len(), 1);
+        assert_eq!(
+            parsed.local_imports[0].scope,
+            "Factory.build.<locals>.inner.<locals>"
+        );
+        assert_eq!(
+            parsed.local_imports[0].import.imported_item,
+            "products.Base"
+        );
+        assert_eq!(parsed.local_imports[0].import.imported_as, "Base");
+    }
+
+    #[test]
+    fn test_dynamic_class_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_dynamic_classes.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+import types
+
+class Base:
+    pass
+
+Foo = type("Foo", (Base, mixins.Mixin), {"x": 1})
+Bar = types.new_class("Bar", (Foo,))
+Empty = types.new_class("Empty")
+NotAClass = type(Foo)
+Unknown = type("Unknown", BASES, {})
+NotImported = new_class("NotImported", (Foo,))
+
+from types import new_class
+
+Imported = new_class("Imported", (Foo,))
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let classes: Vec<(&str, Vec<&str>, bool)> = parsed
+            .classes
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.bases.iter().map(|b| b.name.as_str()).collect(),
+                    c.is_dynamic,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            classes,
+            vec![
+                ("Base", vec![], false),
+                ("Foo", vec!["Base", "mixins.Mixin"], true),
+                ("Bar", vec!["Foo"], true),
+                ("Empty", vec![], true),
+                ("Imported", vec!["Foo"], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_conditional_class_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_conditional_classes.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+import sys
+from typing import TYPE_CHECKING
+
+class Plain:
+    pass
+
+if TYPE_CHECKING:
+    class Checked(Plain):
+        pass
+
+if sys.version_info >= (3, 8):
+    class Compat(Plain):
+        pass
+else:
+    class Compat:
+        pass
+
+try:
+    from fast import Backend
+except ImportError:
+    class Backend(Plain):
+        class Inner:
+            pass
+
+with context():
+    class InWith:
+        pass
+
+for _ in range(1):
+    class InFor:
+        pass
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let guard_of = |name: &str| {
+            parsed
+                .classes
+                .iter()
+                .filter(|c| c.name == name)
+                .map(|c| c.guard.clone())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(guard_of("Plain"), vec![None]);
+        assert_eq!(
+            guard_of("Checked"),
+            vec![Some("if TYPE_CHECKING".to_string())]
+        );
+        assert_eq!(
+            guard_of("Compat"),
+            vec![
+                Some("if sys.version_info >= (3, 8)".to_string()),
+                Some("else".to_string())
+            ]
+        );
+        assert_eq!(
+            guard_of("Backend"),
+            vec![Some("except ImportError".to_string())]
+        );
+        assert_eq!(
+            guard_of("Backend.Inner"),
+            vec![Some("except ImportError".to_string())]
+        );
+        assert_eq!(guard_of("InWith"), vec![Some("with context()".to_string())]);
+        assert_eq!(
+            guard_of("InFor"),
+            vec![Some("for _ in range(1)".to_string())]
+        );
+
+        // Imports inside guarded blocks are recorded too
+        assert!(
+            parsed
+                .imports
+                .iter()
+                .any(|i| i.imported_item == "fast.Backend")
+        );
+    }
+
+    // Parametric tests for import parsing
+    #[derive(Debug)]
+    struct ImportCase {
+        name: &'static str,
+        python_code: &'static str,
+        module_path: &'static str,
+        is_package: bool,
+        expected_imports: Vec<(&'static str, &'static str)>, // (imported_item, imported_as)
+    }
+
+    #[yare::parameterized(
+        absolute_import_simple = { ImportCase {
+            name: "absolute import simple",
+            python_code: "import foo",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo", "foo")],
+        } },
+        absolute_import_dotted = { ImportCase {
+            name: "absolute import dotted",
+            python_code: "import foo.bar.baz",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.bar.baz", "foo.bar.baz")],
+        } },
+        absolute_import_alias = { ImportCase {
+            name: "absolute import with alias",
+            python_code: "import foo as f",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo", "f")],
+        } },
+        absolute_import_dotted_alias = { ImportCase {
+            name: "absolute import dotted with alias",
+            python_code: "import foo.bar as fb",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.bar", "fb")],
+        } },
+        from_import_simple = { ImportCase {
+            name: "from import simple",
+            python_code: "from foo import Bar",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.Bar", "Bar")],
+        } },
+        from_import_dotted = { ImportCase {
+            name: "from import dotted",
+            python_code: "from foo.bar import Baz",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.bar.Baz", "Baz")],
+        } },
+        from_import_alias = { ImportCase {
+            name: "from import with alias",
+            python_code: "from foo import Bar as B",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.Bar", "B")],
+        } },
+        from_import_multiple = { ImportCase {
+            name: "from import multiple",
+            python_code: "from foo import Bar, Baz",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.Bar", "Bar"), ("foo.Baz", "Baz")],
+        } },
+        from_import_multiple_alias = { ImportCase {
+            name: "from import multiple with alias",
+            python_code: "from foo import Bar as B, Baz as Z",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo.Bar", "B"), ("foo.Baz", "Z")],
+        } },
+        relative_import_one_level_module = { ImportCase {
+            name: "relative import one level from module",
+            python_code: "from .sibling import Foo",
+            module_path: "pkg.mymodule",
+            is_package: false,
+            expected_imports: vec![("pkg.sibling.Foo", "Foo")],
+        } },
+        relative_import_one_level_package = { ImportCase {
+            name: "relative import one level from package",
+            python_code: "from .sibling import Foo",
+            module_path: "pkg",
+            is_package: true,
+            expected_imports: vec![("pkg.sibling.Foo", "Foo")],
+        } },
+        relative_import_two_levels_module = { ImportCase {
+            name: "relative import two levels from module",
+            python_code: "from ..other import Foo",
+            module_path: "pkg.sub.mymodule",
+            is_package: false,
+            expected_imports: vec![("pkg.other.Foo", "Foo")],
+        } },
+        relative_import_two_levels_package = { ImportCase {
+            name: "relative import two levels from package",
+            python_code: "from ..other import Foo",
+            module_path: "pkg.sub",
+            is_package: true,
+            expected_imports: vec![("pkg.other.Foo", "Foo")],
+        } },
+        relative_import_no_module = { ImportCase {
+            name: "relative import without from module",
+            python_code: "from . import Foo",
+            module_path: "pkg.mymodule",
+            is_package: false,
+            expected_imports: vec![("pkg.Foo", "Foo")],
+        } },
+        relative_import_alias = { ImportCase {
+            name: "relative import with alias",
+            python_code: "from .sibling import Foo as F",
+            module_path: "pkg.mymodule",
+            is_package: false,
+            expected_imports: vec![("pkg.sibling.Foo", "F")],
+        } },
+        multiple_imports = { ImportCase {
+            name: "multiple import statements",
+            python_code: "import foo\nfrom bar import Baz",
+            module_path: "mymodule",
+            is_package: false,
+            expected_imports: vec![("foo", "foo"), ("bar.Baz", "Baz")],
+        } },
+    )]
+    fn test_import_parsing(case: ImportCase) {
+        let temp_dir = std::env::temp_dir();
+        // Create a proper directory structure for package tests
+        let test_id = case.name.replace(' ', "_");
+        let test_root = temp_dir.join(format!("test_imports_{test_id}"));
+        std::fs::create_dir_all(&test_root).unwrap();
+
+        let temp_file = if case.is_package {
+            test_root.join("__init__.py")
+        } else {
+            test_root.join("test.py")
+        };
+
+        std::fs::write(&temp_file, case.python_code).unwrap();
+
+        let parsed = parse_file(&temp_file, case.module_path).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_dir_all(&test_root);
+
+        // Verify imports
+        assert_eq!(
+            parsed.imports.len(),
+            case.expected_imports.len(),
+            "Case '{}': expected {} imports, got {}",
+            case.name,
+            case.expected_imports.len(),
+            parsed.imports.len()
+        );
+
+        for (i, (expected_item, expected_as)) in case.expected_imports.iter().enumerate() {
+            assert_eq!(
+                parsed.imports[i].imported_item, *expected_item,
+                "Case '{}': import {} - expected imported_item '{}', got '{}'",
+                case.name, i, expected_item, parsed.imports[i].imported_item
+            );
+            assert_eq!(
+                parsed.imports[i].imported_as, *expected_as,
+                "Case '{}': import {} - expected imported_as '{}', got '{}'",
+                case.name, i, expected_as, parsed.imports[i].imported_as
+            );
+        }
+    }
+
+    // Parametric tests for `__all__` extraction
+    #[derive(Debug)]
+    struct DunderAllCase {
+        name: &'static str,
+        python_code: &'static str,
+        expected_all: Option<Vec<&'static str>>,
+    }
+
+    #[yare::parameterized(
+        no_all = { DunderAllCase {
+            name: "no all",
+            python_code: "class Foo: pass",
+            expected_all: None,
+        } },
+        list = { DunderAllCase {
+            name: "list",
+            python_code: "__all__ = ['Foo', 'Bar']",
+            expected_all: Some(vec!["Foo", "Bar"]),
+        } },
+        tuple = { DunderAllCase {
+            name: "tuple",
+            python_code: "__all__ = ('Foo',)",
+            expected_all: Some(vec!["Foo"]),
+        } },
+        annotated = { DunderAllCase {
+            name: "annotated",
+            python_code: "__all__: list[str] = ['Foo']",
+            expected_all: Some(vec!["Foo"]),
+        } },
+        augmented = { DunderAllCase {
+            name: "augmented",
+            python_code: "__all__ = ['Foo']\n__all__ += ['Bar']",
+            expected_all: Some(vec!["Foo", "Bar"]),
+        } },
+        concatenated = { DunderAllCase {
+            name: "concatenated",
+            python_code: "__all__ = ['Foo'] + ['Bar']",
+            expected_all: Some(vec!["Foo", "Bar"]),
+        } },
+        reassigned = { DunderAllCase {
+            name: "reassigned",
+            python_code: "__all__ = ['Foo']\n__all__ = ['Bar']",
+            expected_all: Some(vec!["Bar"]),
+        } },
+        class_level_ignored = { DunderAllCase {
+            name: "class level ignored",
+            python_code: "class Foo:\n    __all__ = ['Bar']",
+            expected_all: None,
+        } },
+    )]
+    fn test_dunder_all_extraction(case: DunderAllCase) {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join(format!("test_all_{}.py", case.name.replace(' ', "_")));
+
+        std::fs::write(&temp_file, case.python_code).unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let expected_all = case
+            .expected_all
+            .map(|names| names.iter().map(|n| n.to_string()).collect::<Vec<_>>());
+        assert_eq!(parsed.all_names, expected_all, "Case '{}'", case.name);
+    }
+
+    #[test]
+    fn test_base_forwarding_calls() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_base_forwarding_calls.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+class Foo(make_base(Bar, Baz)): pass
+class Qux(compat.make_base(Bar)): pass
+class Quux(other.helper(Bar)): pass
+"#,
+        )
+        .unwrap();
+
+        let options = ParseOptions {
+            base_forwarding_calls: vec!["make_base".to_string()],
+            ..ParseOptions::default()
+        };
+        let parsed = parse_file_with_options(&temp_file, "test_module", &options).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let bases_of = |name: &str| {
+            parsed
+                .classes
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.bases.iter().map(|b| b.name.clone()).collect::<Vec<_>>())
+                .unwrap()
+        };
+
+        assert_eq!(bases_of("Foo"), vec!["Bar", "Baz"]);
+        assert_eq!(bases_of("Qux"), vec!["Bar"]);
+        assert!(bases_of("Quux").is_empty());
+    }
+
+    #[test]
+    fn test_metaclass_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_metaclass_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+import six
+
+class Foo(Base, metaclass=abc.ABCMeta): pass
+class Bar(six.with_metaclass(Meta, Base)): pass
+
+@six.add_metaclass(Meta)
+class Baz: pass
+
+class Qux(Base): pass
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let metaclass_of = |name: &str| {
+            parsed
+                .classes
+                .iter()
+                .find(|c| c.name == name)
+                .map(|c| c.metaclass.clone())
+                .unwrap()
+        };
+
+        assert_eq!(metaclass_of("Foo"), Some("abc.ABCMeta".to_string()));
+        assert_eq!(metaclass_of("Bar"), Some("Meta".to_string()));
+        assert_eq!(metaclass_of("Baz"), Some("Meta".to_string()));
+        assert_eq!(metaclass_of("Qux"), None);
+    }
+
+    #[test]
+    fn test_location_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_location_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"import abc
+
+class Foo(
+    Bar,
+    pkg.Baz[int],
+):
+    pass
+
+if True:
+    class Qux: pass
+
+Dyn = type("Dyn", (Foo,), {})
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let class = |name: &str| parsed.classes.iter().find(|c| c.name == name).unwrap();
+
+        let foo = class("Foo");
+        assert_eq!(
+            foo.location,
+            Location {
+                line: 3,
+                column: 1,
+                end_line: 7,
+                end_column: 9,
+                cell: None,
+            }
+        );
+        assert_eq!(
+            foo.bases[0].location,
+            Location {
+                line: 4,
+                column: 5,
+                end_line: 4,
+                end_column: 8,
+                cell: None,
+            }
+        );
+        assert_eq!(
+            foo.bases[1].location,
+            Location {
+                line: 5,
+                column: 5,
+                end_line: 5,
+                end_column: 17,
+                cell: None,
+            }
+        );
+
+        assert_eq!(class("Qux").location.to_string(), "10:5");
+        assert_eq!(class("Dyn").location.to_string(), "12:1");
+    }
+
+    #[test]
+    fn test_notebook_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_notebook_extraction.ipynb");
+
+        std::fs::write(
+            &temp_file,
+            r##"{
+ "cells": [
+  {"cell_type": "markdown", "metadata": {}, "source": ["# Models"]},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["%matplotlib inline\n", "!pip install foo\n", "from base import Model"]},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": "%%bash\necho hello"},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["\n", "class User(Model):\n", "    pass\n"]}
+ ],
+ "metadata": {"language_info": {"name": "python"}},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        assert_eq!(parsed.imports.len(), 1);
+        assert_eq!(parsed.imports[0].imported_item, "base.Model");
+
+        assert_eq!(parsed.classes.len(), 1);
+        let class = &parsed.classes[0];
+        assert_eq!(class.name, "User");
+        assert_eq!(class.bases[0].name, "Model");
+        assert_eq!(class.location.cell, Some(3));
+        assert_eq!(class.location.line, 2);
+        assert_eq!(class.location.to_string(), "cell3:2:1");
+    }
+
+    // Parametric tests for source decoding
+    #[derive(Debug)]
+    struct EncodingCase {
+        name: &'static str,
+        source: &'static [u8],
+        expected_class: Option<&'static str>,
+    }
+
+    #[yare::parameterized(
+        utf8 = { EncodingCase {
+            name: "utf8",
+            source: "class Caf\u{e9}(Base): pass\n".as_bytes(),
+            expected_class: Some("Caf\u{e9}"),
+        } },
+        utf8_bom = { EncodingCase {
+            name: "utf8 bom",
+            source: b"\xEF\xBB\xBFclass Foo(Base): pass\n",
+            expected_class: Some("Foo"),
+        } },
+        latin1_cookie = { EncodingCase {
+            name: "latin1 cookie",
+            source: b"# -*- coding: latin-1 -*-\nclass Caf\xE9(Base): pass\n",
+            expected_class: Some("Caf\u{e9}"),
+        } },
+        cookie_on_second_line = { EncodingCase {
+            name: "cookie on second line",
+            source: b"#!/usr/bin/env python\n# vim: set fileencoding=iso-8859-1 :\nclass Caf\xE9(Base): pass\n",
+            expected_class: Some("Caf\u{e9}"),
+        } },
+        cp1252_cookie = { EncodingCase {
+            name: "cp1252 cookie",
+            source: b"# coding=cp1252\nclass Foo(Base):\n    \"\x93quoted\x94\"\n",
+            expected_class: Some("Foo"),
+        } },
+        cookie_after_code_ignored = { EncodingCase {
+            name: "cookie after code ignored",
+            source: b"import os\n# coding: latin-1\nclass Caf\xE9(Base): pass\n",
+            expected_class: None,
+        } },
+        invalid_utf8 = { EncodingCase {
+            name: "invalid utf8",
+            source: b"class Foo(Base): pass\n# caf\xE9\n",
+            expected_class: None,
+        } },
+    )]
+    fn test_source_decoding(case: EncodingCase) {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join(format!("test_encoding_{}.py", case.name.replace(' ', "_")));
+
+        std::fs::write(&temp_file, case.source).unwrap();
+
+        let result = parse_file(&temp_file, "test_module");
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        match case.expected_class {
+            Some(expected_class) => {
+                let parsed = result.unwrap();
+                assert_eq!(parsed.classes.len(), 1, "Case '{}'", case.name);
+                assert_eq!(
+                    parsed.classes[0].name, expected_class,
+                    "Case '{}'",
+                    case.name
+                );
+            }
+            None => assert!(
+                matches!(result, Err(Error::DecodeError { .. })),
+                "Case '{}': expected a decode error, got {:?}",
+                case.name,
+                result
+            ),
+        }
+    }
+
+    #[test]
+    fn test_error_tolerant_parsing() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_error_tolerant_parsing.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"from base import Animal
+
+class Dog(Animal):
+    pass
+
+def broken(:
+    pass
+
+@decorator
+class Cat(Animal):
+    def speak(self):
+        return "meow"
+
+class Cow(Animal)
+    pass
+
+class Horse(Animal):
+    pass
+"#,
+        )
+        .unwrap();
+
+        // Without error tolerance the whole file fails
+        let result = parse_file(&temp_file, "test_module");
+        assert!(matches!(result, Err(Error::ParseError { .. })));
+
+        let options = ParseOptions {
+            error_tolerant: true,
+            ..ParseOptions::default()
+        };
+        let parsed = parse_file_with_options(&temp_file, "test_module", &options).unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        assert!(parsed.is_partial);
+        assert_eq!(parsed.imports.len(), 1);
+
+        let class_names: Vec<&str> = parsed.classes.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(class_names, vec!["Dog", "Cat", "Horse"]);
+
+        // Locations after the skipped code are unchanged
+        let horse = parsed.classes.iter().find(|c| c.name == "Horse").unwrap();
+        assert_eq!(horse.location.line, 17);
+    }
+
+    #[test]
+    fn test_registration_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_registration_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+import abc
+from collections.abc import Sized
+
+Sized.register(Foo)
+abc.Base.register(bar.Bar)
+
+@Base.register
+class Baz: pass
+
+class Outer:
+    @Base.register
+    class Inner: pass
+
+if True:
+    Base.register(Qux)
+
+Base.register(Foo, Bar)
+dispatch.register(int, handler)
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let registrations: Vec<(&str, &str)> = parsed
+            .registrations
+            .iter()
+            .map(|r| (r.base.as_str(), r.subclass.as_str()))
+            .collect();
+        assert_eq!(
+            registrations,
+            vec![
+                ("Sized", "Foo"),
+                ("abc.Base", "bar.Bar"),
+                ("Base", "Baz"),
+                ("Base", "Outer.Inner"),
+                ("Base", "Qux"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_member_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_member_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+class Shape:
+    sides = 4
+    name: str
+    x, y = 0, 0
+
+    def area(self): ...
+
+    async def render(self): ...
+
+    if sys.version_info >= (3, 8):
+        def perimeter(self): ...
+    else:
+        def perimeter(self): ...
+
+    try:
+        import numpy
+    except ImportError:
+        numpy = None
+
+    class Meta:
+        ordering = "name"
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let members = |class_name: &str| -> Vec<(String, MemberKind)> {
+            parsed
+                .classes
+                .iter()
+                .find(|c| c.name == class_name)
+                .unwrap()
+                .members
+                .iter()
+                .map(|m| (m.name.clone(), m.kind))
+                .collect()
+        };
+        assert_eq!(
+            members("Shape"),
+            vec![
+                ("sides".to_string(), MemberKind::Attribute),
+                ("name".to_string(), MemberKind::Attribute),
+                ("x".to_string(), MemberKind::Attribute),
+                ("y".to_string(), MemberKind::Attribute),
+                ("area".to_string(), MemberKind::Method),
+                ("render".to_string(), MemberKind::Method),
+                ("perimeter".to_string(), MemberKind::Method),
+                ("numpy".to_string(), MemberKind::Attribute),
+            ]
+        );
+        assert_eq!(
+            members("Shape.Meta"),
+            vec![("ordering".to_string(), MemberKind::Attribute)]
+        );
+    }
+
+    #[test]
+    fn test_member_decorator_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_member_decorator_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+class Shape(abc.ABC):
+    @property
+    def area(self): ...
+
+    @area.setter
+    def area(self, value): ...
+
+    @classmethod
+    def create(cls): ...
+
+    @staticmethod
+    def validate(): ...
+
+    @abc.abstractmethod
+    def draw(self): ...
+
+    @functools.lru_cache(maxsize=None)
+    def perimeter(self): ...
+
+    sides = 4
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let members = &parsed.classes[0].members;
+        let member = |name: &str| members.iter().find(|m| m.name == name).unwrap();
+
+        assert_eq!(members.len(), 6);
+        assert_eq!(member("area").decorators, vec!["property"]);
+        assert!(member("area").is_property());
+        assert!(member("create").is_classmethod());
+        assert!(member("validate").is_staticmethod());
+        assert_eq!(member("draw").decorators, vec!["abc.abstractmethod"]);
+        assert!(member("draw").is_abstract());
+        assert_eq!(member("perimeter").decorators, vec!["functools.lru_cache"]);
+        assert!(!member("perimeter").is_property());
+        assert_eq!(member("sides").kind, MemberKind::Attribute);
+        assert!(member("sides").decorators.is_empty());
+    }
+
+    #[test]
+    fn test_class_decorator_extraction() {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join("test_class_decorator_extraction.py");
+
+        std::fs::write(
+            &temp_file,
+            r#"
+@dataclass
+class Point: pass
+
+@attrs.define(frozen=True)
+@register
+class Config: pass
+
+@register_backend("s3", -1, (1.5, None), ["a"], priority=10, factory=make, *args)
+class S3Backend: pass
+
+@register_backend(NAME, "fallback")
+class Fallback: pass
+
+class Plain: pass
+"#,
+        )
+        .unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let decorators: Vec<(&str, Vec<String>)> = parsed
+            .classes
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.decorators.iter().map(ToString::to_string).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            decorators,
+            vec![
+                ("Point", vec!["@dataclass".to_string()]),
+                (
+                    "Config",
+                    vec![
+                        "@attrs.define(frozen=True)".to_string(),
+                        "@register".to_string()
+                    ]
+                ),
+                (
+                    "S3Backend",
+                    vec![
+                        r#"@register_backend("s3", -1, (1.5, None), ["a"], *args, priority=10, factory=make)"#
+                            .to_string()
+                    ]
+                ),
+                (
+                    "Fallback",
+                    vec![r#"@register_backend(NAME, "fallback")"#.to_string()]
+                ),
+                ("Plain", vec![]),
+            ]
         );
-
-        // Top-level module
-        let path = Path::new("/project/src/module.py");
-        let root = Path::new("/project/src");
         assert_eq!(
-            file_path_to_module_path(path, root),
-            Some("module".to_string())
+            parsed.classes[2].decorators[0].args[0],
+            LiteralValue::Str("s3".to_string())
         );
-
-        // Top-level __init__.py (edge case)
-        let path = Path::new("/project/src/__init__.py");
-        let root = Path::new("/project/src");
-        assert_eq!(file_path_to_module_path(path, root), None);
-    }
-
-    #[test]
-    fn test_file_path_to_module_path_relative() {
-        // Test with matching prefixes
-        let path = Path::new("./foo/bar.py");
-        let root = Path::new(".");
+        // Arguments which aren't literals keep their positions
         assert_eq!(
-            file_path_to_module_path(path, root),
-            Some("foo.bar".to_string())
+            parsed.classes[3].decorators[0].args,
+            vec![
+                LiteralValue::Expr("NAME".to_string()),
+                LiteralValue::Str("fallback".to_string())
+            ]
         );
     }
 
     #[test]
-    fn test_nested_class_extraction() {
-        // Create a temporary Python file with nested classes
+    fn test_attribute_value_extraction() {
         let temp_dir = std::env::temp_dir();
-        let temp_file = temp_dir.join("test_nested_classes.py");
+        let temp_file = temp_dir.join("test_attribute_value_extraction.py");
 
         std::fs::write(
             &temp_file,
             r#"
-class Foo:
-    pass
-
-class Bar:
-    class NestedInBar(Foo):
-        pass
-
-    class AnotherNested:
-        class DoublyNested(Foo):
-            pass
-
-class TopLevel(Foo):
-    pass
+class Plugin:
+    name = "s3"
+    priority = -10
+    ratio: float = 0.5
+    abstract = True
+    parent = None
+    formats = ("csv", ("json", 1))
+    aliases = ["a", "b"]
+    factory = make_factory()
+    label: str
+    x = y = 1
+    a, b = 1, 2
+
+class Redefined:
+    name = "a"
+    level: int
+    name = "b"
+    if DEBUG:
+        name = "debug"
+    level = 3
+    def method(self): ...
+    method = None
 "#,
         )
         .unwrap();
@@ -466,202 +2696,137 @@ class TopLevel(Foo):
         // Clean up
         let _ = std::fs::remove_file(&temp_file);
 
-        // Check that we found all classes with correct names
-        let class_names: Vec<&str> = parsed.classes.iter().map(|c| c.name.as_str()).collect();
-
-        assert_eq!(class_names.len(), 6);
-        assert!(class_names.contains(&"Foo"));
-        assert!(class_names.contains(&"Bar"));
-        assert!(class_names.contains(&"Bar.NestedInBar"));
-        assert!(class_names.contains(&"Bar.AnotherNested"));
-        assert!(class_names.contains(&"Bar.AnotherNested.DoublyNested"));
-        assert!(class_names.contains(&"TopLevel"));
-
-        // Verify that NestedInBar has Foo as a base
-        let nested_in_bar = parsed
-            .classes
+        let values: Vec<(&str, Option<String>)> = parsed.classes[0]
+            .members
             .iter()
-            .find(|c| c.name == "Bar.NestedInBar")
-            .unwrap();
-        assert_eq!(nested_in_bar.bases.len(), 1);
-        assert_eq!(nested_in_bar.bases[0], "Foo");
+            .map(|m| (m.name.as_str(), m.value.as_ref().map(ToString::to_string)))
+            .collect();
+        let some = |value: &str| Some(value.to_string());
+        assert_eq!(
+            values,
+            vec![
+                ("name", some(r#""s3""#)),
+                ("priority", some("-10")),
+                ("ratio", some("0.5")),
+                ("abstract", some("True")),
+                ("parent", some("None")),
+                ("formats", some(r#"("csv", ("json", 1))"#)),
+                ("aliases", some(r#"["a", "b"]"#)),
+                ("factory", None),
+                ("label", None),
+                ("x", some("1")),
+                ("y", some("1")),
+                ("a", None),
+                ("b", None),
+            ]
+        );
 
-        // Verify that DoublyNested has Foo as a base
-        let doubly_nested = parsed
-            .classes
+        let annotation = |name: &str| {
+            parsed.classes[0]
+                .members
+                .iter()
+                .find(|m| m.name == name)
+                .and_then(|m| m.annotation.clone())
+        };
+        assert_eq!(annotation("ratio"), Some("float".to_string()));
+        assert_eq!(annotation("label"), Some("str".to_string()));
+        assert_eq!(annotation("name"), None);
+
+        // The last unconditional definition wins, keeping the annotation
+        let redefined: Vec<(&str, MemberKind, Option<String>, Option<&str>)> = parsed.classes[1]
+            .members
             .iter()
-            .find(|c| c.name == "Bar.AnotherNested.DoublyNested")
-            .unwrap();
-        assert_eq!(doubly_nested.bases.len(), 1);
-        assert_eq!(doubly_nested.bases[0], "Foo");
+            .map(|m| {
+                (
+                    m.name.as_str(),
+                    m.kind,
+                    m.value.as_ref().map(ToString::to_string),
+                    m.annotation.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            redefined,
+            vec![
+                ("name", MemberKind::Attribute, some(r#""b""#), None),
+                ("level", MemberKind::Attribute, some("3"), Some("int")),
+                ("method", MemberKind::Attribute, some("None"), None),
+            ]
+        );
     }
 
-    // Parametric tests for import parsing
+    // Parametric tests for alias extraction
     #[derive(Debug)]
-    struct ImportCase {
+    struct AliasCase {
         name: &'static str,
         python_code: &'static str,
-        module_path: &'static str,
-        is_package: bool,
-        expected_imports: Vec<(&'static str, &'static str)>, // (imported_item, imported_as)
+        expected_aliases: Vec<(&'static str, &'static str)>, // (name, target)
     }
 
     #[yare::parameterized(
-        absolute_import_simple = { ImportCase {
-            name: "absolute import simple",
-            python_code: "import foo",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo", "foo")],
-        } },
-        absolute_import_dotted = { ImportCase {
-            name: "absolute import dotted",
-            python_code: "import foo.bar.baz",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.bar.baz", "foo.bar.baz")],
-        } },
-        absolute_import_alias = { ImportCase {
-            name: "absolute import with alias",
-            python_code: "import foo as f",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo", "f")],
-        } },
-        absolute_import_dotted_alias = { ImportCase {
-            name: "absolute import dotted with alias",
-            python_code: "import foo.bar as fb",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.bar", "fb")],
-        } },
-        from_import_simple = { ImportCase {
-            name: "from import simple",
-            python_code: "from foo import Bar",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.Bar", "Bar")],
-        } },
-        from_import_dotted = { ImportCase {
-            name: "from import dotted",
-            python_code: "from foo.bar import Baz",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.bar.Baz", "Baz")],
-        } },
-        from_import_alias = { ImportCase {
-            name: "from import with alias",
-            python_code: "from foo import Bar as B",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.Bar", "B")],
-        } },
-        from_import_multiple = { ImportCase {
-            name: "from import multiple",
-            python_code: "from foo import Bar, Baz",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.Bar", "Bar"), ("foo.Baz", "Baz")],
+        name_alias = { AliasCase {
+            name: "name alias",
+            python_code: "Base = Model",
+            expected_aliases: vec![("Base", "Model")],
         } },
-        from_import_multiple_alias = { ImportCase {
-            name: "from import multiple with alias",
-            python_code: "from foo import Bar as B, Baz as Z",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo.Bar", "B"), ("foo.Baz", "Z")],
+        attribute_alias = { AliasCase {
+            name: "attribute alias",
+            python_code: "Base = models.Model",
+            expected_aliases: vec![("Base", "models.Model")],
         } },
-        relative_import_one_level_module = { ImportCase {
-            name: "relative import one level from module",
-            python_code: "from .sibling import Foo",
-            module_path: "pkg.mymodule",
-            is_package: false,
-            expected_imports: vec![("pkg.sibling.Foo", "Foo")],
+        chained_targets = { AliasCase {
+            name: "chained targets",
+            python_code: "A = B = models.Model",
+            expected_aliases: vec![("A", "models.Model"), ("B", "models.Model")],
         } },
-        relative_import_one_level_package = { ImportCase {
-            name: "relative import one level from package",
-            python_code: "from .sibling import Foo",
-            module_path: "pkg",
-            is_package: true,
-            expected_imports: vec![("pkg.sibling.Foo", "Foo")],
+        annotated_alias = { AliasCase {
+            name: "annotated alias",
+            python_code: "Base: TypeAlias = models.Model",
+            expected_aliases: vec![("Base", "models.Model")],
         } },
-        relative_import_two_levels_module = { ImportCase {
-            name: "relative import two levels from module",
-            python_code: "from ..other import Foo",
-            module_path: "pkg.sub.mymodule",
-            is_package: false,
-            expected_imports: vec![("pkg.other.Foo", "Foo")],
+        type_statement = { AliasCase {
+            name: "type statement",
+            python_code: "type Base = models.Model",
+            expected_aliases: vec![("Base", "models.Model")],
         } },
-        relative_import_two_levels_package = { ImportCase {
-            name: "relative import two levels from package",
-            python_code: "from ..other import Foo",
-            module_path: "pkg.sub",
-            is_package: true,
-            expected_imports: vec![("pkg.other.Foo", "Foo")],
+        guarded_alias = { AliasCase {
+            name: "guarded alias",
+            python_code: "try:\n    Base = a.Model\nexcept ImportError:\n    Base = b.Model",
+            expected_aliases: vec![("Base", "a.Model"), ("Base", "b.Model")],
         } },
-        relative_import_no_module = { ImportCase {
-            name: "relative import without from module",
-            python_code: "from . import Foo",
-            module_path: "pkg.mymodule",
-            is_package: false,
-            expected_imports: vec![("pkg.Foo", "Foo")],
+        call_ignored = { AliasCase {
+            name: "call ignored",
+            python_code: "Base = declarative_base()",
+            expected_aliases: vec![],
         } },
-        relative_import_alias = { ImportCase {
-            name: "relative import with alias",
-            python_code: "from .sibling import Foo as F",
-            module_path: "pkg.mymodule",
-            is_package: false,
-            expected_imports: vec![("pkg.sibling.Foo", "F")],
+        literal_ignored = { AliasCase {
+            name: "literal ignored",
+            python_code: "VERSION = 1",
+            expected_aliases: vec![],
         } },
-        multiple_imports = { ImportCase {
-            name: "multiple import statements",
-            python_code: "import foo\nfrom bar import Baz",
-            module_path: "mymodule",
-            is_package: false,
-            expected_imports: vec![("foo", "foo"), ("bar.Baz", "Baz")],
+        class_level_ignored = { AliasCase {
+            name: "class level ignored",
+            python_code: "class Foo:\n    Base = models.Model",
+            expected_aliases: vec![],
         } },
     )]
-    fn test_import_parsing(case: ImportCase) {
+    fn test_alias_extraction(case: AliasCase) {
         let temp_dir = std::env::temp_dir();
-        // Create a proper directory structure for package tests
-        let test_id = case.name.replace(' ', "_");
-        let test_root = temp_dir.join(format!("test_imports_{test_id}"));
-        std::fs::create_dir_all(&test_root).unwrap();
-
-        let temp_file = if case.is_package {
-            test_root.join("__init__.py")
-        } else {
-            test_root.join("test.py")
-        };
+        let temp_file = temp_dir.join(format!("test_aliases_{}.py", case.name.replace(' ', "_")));
 
         std::fs::write(&temp_file, case.python_code).unwrap();
 
-        let parsed = parse_file(&temp_file, case.module_path).unwrap();
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
 
         // Clean up
-        let _ = std::fs::remove_dir_all(&test_root);
-
-        // Verify imports
-        assert_eq!(
-            parsed.imports.len(),
-            case.expected_imports.len(),
-            "Case '{}': expected {} imports, got {}",
-            case.name,
-            case.expected_imports.len(),
-            parsed.imports.len()
-        );
+        let _ = std::fs::remove_file(&temp_file);
 
-        for (i, (expected_item, expected_as)) in case.expected_imports.iter().enumerate() {
-            assert_eq!(
-                parsed.imports[i].imported_item, *expected_item,
-                "Case '{}': import {} - expected imported_item '{}', got '{}'",
-                case.name, i, expected_item, parsed.imports[i].imported_item
-            );
-            assert_eq!(
-                parsed.imports[i].imported_as, *expected_as,
-                "Case '{}': import {} - expected imported_as '{}', got '{}'",
-                case.name, i, expected_as, parsed.imports[i].imported_as
-            );
-        }
+        let aliases: Vec<(&str, &str)> = parsed
+            .aliases
+            .iter()
+            .map(|a| (a.name.as_str(), a.target.as_str()))
+            .collect();
+        assert_eq!(aliases, case.expected_aliases, "Case '{}'", case.name);
     }
 
     // Parametric tests for base class extraction
@@ -734,6 +2899,36 @@ class TopLevel(Foo):
             class_name: "Foo",
             expected_bases: vec!["typing.Generic"],
         } },
+        six_with_metaclass = { BaseClassCase {
+            name: "six with_metaclass",
+            python_code: "class Foo(six.with_metaclass(Meta, Bar, Baz)): pass",
+            class_name: "Foo",
+            expected_bases: vec!["Bar", "Baz"],
+        } },
+        future_with_metaclass = { BaseClassCase {
+            name: "future with_metaclass",
+            python_code: "class Foo(future.utils.with_metaclass(Meta, Bar), Mixin): pass",
+            class_name: "Foo",
+            expected_bases: vec!["Bar", "Mixin"],
+        } },
+        bare_with_metaclass = { BaseClassCase {
+            name: "bare with_metaclass",
+            python_code: "class Foo(with_metaclass(Meta, Generic[T])): pass",
+            class_name: "Foo",
+            expected_bases: vec!["Generic"],
+        } },
+        unknown_call = { BaseClassCase {
+            name: "unknown call",
+            python_code: "class Foo(make_base(Bar)): pass",
+            class_name: "Foo",
+            expected_bases: vec![],
+        } },
+        new_type = { BaseClassCase {
+            name: "new type",
+            python_code: "UserId = NewType('UserId', Base)",
+            class_name: "UserId",
+            expected_bases: vec!["Base"],
+        } },
     )]
     fn test_base_class_extraction(case: BaseClassCase) {
         let temp_dir = std::env::temp_dir();
@@ -770,10 +2965,75 @@ class TopLevel(Foo):
 
         for (i, expected_base) in case.expected_bases.iter().enumerate() {
             assert_eq!(
-                class.bases[i], *expected_base,
+                class.bases[i].name, *expected_base,
                 "Case '{}': base {} - expected '{}', got '{}'",
-                case.name, i, expected_base, class.bases[i]
+                case.name, i, expected_base, class.bases[i].name
             );
         }
     }
+
+    // Parametric tests for base class type argument extraction
+    #[derive(Debug)]
+    struct TypeArgsCase {
+        name: &'static str,
+        python_code: &'static str,
+        expected_type_args: Vec<Vec<&'static str>>,
+    }
+
+    #[yare::parameterized(
+        no_subscript = { TypeArgsCase {
+            name: "no subscript",
+            python_code: "class Foo(Bar): pass",
+            expected_type_args: vec![vec![]],
+        } },
+        single_arg = { TypeArgsCase {
+            name: "single arg",
+            python_code: "class Foo(Repository[User]): pass",
+            expected_type_args: vec![vec!["User"]],
+        } },
+        attribute_arg = { TypeArgsCase {
+            name: "attribute arg",
+            python_code: "class Foo(Repository[models.User]): pass",
+            expected_type_args: vec![vec!["models.User"]],
+        } },
+        multiple_args = { TypeArgsCase {
+            name: "multiple args",
+            python_code: "class Foo(Mapping[str, int], Mixin): pass",
+            expected_type_args: vec![vec!["str", "int"], vec![]],
+        } },
+        nested_subscript = { TypeArgsCase {
+            name: "nested subscript",
+            python_code: "class Foo(Repository[list[User]]): pass",
+            expected_type_args: vec![vec!["list[User]"]],
+        } },
+        forward_reference = { TypeArgsCase {
+            name: "forward reference",
+            python_code: "class Foo(Repository['User']): pass",
+            expected_type_args: vec![vec!["User"]],
+        } },
+        forwarded_base = { TypeArgsCase {
+            name: "forwarded base",
+            python_code: "class Foo(with_metaclass(Meta, Repository[User])): pass",
+            expected_type_args: vec![vec!["User"]],
+        } },
+    )]
+    fn test_base_type_args_extraction(case: TypeArgsCase) {
+        let temp_dir = std::env::temp_dir();
+        let temp_file = temp_dir.join(format!("test_type_args_{}.py", case.name.replace(' ', "_")));
+
+        std::fs::write(&temp_file, case.python_code).unwrap();
+
+        let parsed = parse_file(&temp_file, "test_module").unwrap();
+
+        // Clean up
+        let _ = std::fs::remove_file(&temp_file);
+
+        let class = parsed.classes.iter().find(|c| c.name == "Foo").unwrap();
+        let type_args: Vec<Vec<&str>> = class
+            .bases
+            .iter()
+            .map(|b| b.type_args.iter().map(|a| a.as_str()).collect())
+            .collect();
+        assert_eq!(type_args, case.expected_type_args, "Case '{}'", case.name);
+    }
 }
@@ -5,15 +5,121 @@
 //! modules, handling imports and re-exports correctly.
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, hash_map::Entry},
     path::PathBuf,
 };
 
 use crate::{
     error::Result,
-    parser::{Import, ParsedFile},
+    parser::{
+        BaseClass, ClassDecorator, ClassMember, Import, LocalImport, Location, NameAlias,
+        ParsedFile, Registration,
+    },
 };
 
+/// The classes defined in the `builtins` module which aren't exceptions. Builtin classes
+/// can be used as base classes without being imported.
+const BUILTIN_CLASSES: &[&str] = &[
+    "object",
+    "type",
+    "int",
+    "float",
+    "complex",
+    "bool",
+    "str",
+    "bytes",
+    "bytearray",
+    "memoryview",
+    "list",
+    "tuple",
+    "dict",
+    "set",
+    "frozenset",
+    "range",
+    "slice",
+    "property",
+    "classmethod",
+    "staticmethod",
+    "super",
+];
+
+/// The exception classes defined in the `builtins` module, i.e. `BaseException` and the
+/// classes deriving from it.
+const BUILTIN_EXCEPTIONS: &[&str] = &[
+    "BaseException",
+    "BaseExceptionGroup",
+    "Exception",
+    "ExceptionGroup",
+    "ArithmeticError",
+    "AssertionError",
+    "AttributeError",
+    "BlockingIOError",
+    "BrokenPipeError",
+    "BufferError",
+    "ChildProcessError",
+    "ConnectionAbortedError",
+    "ConnectionError",
+    "ConnectionRefusedError",
+    "ConnectionResetError",
+    "EOFError",
+    "EnvironmentError",
+    "FileExistsError",
+    "FileNotFoundError",
+    "FloatingPointError",
+    "GeneratorExit",
+    "IOError",
+    "ImportError",
+    "IndentationError",
+    "IndexError",
+    "InterruptedError",
+    "IsADirectoryError",
+    "KeyError",
+    "KeyboardInterrupt",
+    "LookupError",
+    "MemoryError",
+    "ModuleNotFoundError",
+    "NameError",
+    "NotADirectoryError",
+    "NotImplementedError",
+    "OSError",
+    "OverflowError",
+    "PermissionError",
+    "ProcessLookupError",
+    "RecursionError",
+    "ReferenceError",
+    "RuntimeError",
+    "StopAsyncIteration",
+    "StopIteration",
+    "SyntaxError",
+    "SystemError",
+    "SystemExit",
+    "TabError",
+    "TimeoutError",
+    "TypeError",
+    "UnboundLocalError",
+    "UnicodeDecodeError",
+    "UnicodeEncodeError",
+    "UnicodeError",
+    "UnicodeTranslateError",
+    "ValueError",
+    "ZeroDivisionError",
+    "Warning",
+    "BytesWarning",
+    "DeprecationWarning",
+    "EncodingWarning",
+    "FutureWarning",
+    "ImportWarning",
+    "PendingDeprecationWarning",
+    "ResourceWarning",
+    "RuntimeWarning",
+    "SyntaxWarning",
+    "UnicodeWarning",
+    "UserWarning",
+];
+
+/// The suffix of the scope of the names local to a function (e.g. "make.<locals>").
+const LOCALS: &str = ".<locals>";
+
 /// Type alias for Python module names (e.g., "foo.bar.baz").
 pub type ModuleName = String;
 
@@ -22,8 +128,10 @@ pub type ModuleName = String;
 pub struct ModuleMetadata {
     /// The file system path to this module.
     pub file_path: PathBuf,
-    /// Whether this is a package (`__init__.py` file).
+    /// Whether this is a package (`__init__.py` or `__init__.pyi` file).
     pub is_package: bool,
+    /// The files of this module which were only partially parsed, due to syntax errors.
+    pub partial_files: Vec<PathBuf>,
 }
 
 /// Metadata about a Python class definition.
@@ -31,9 +139,34 @@ pub struct ModuleMetadata {
 pub struct ClassMetadata {
     /// The base classes this class inherits from.
     ///
-    /// These are stored as unresolved strings (e.g., "Foo" or "module.Foo")
+    /// These are stored unresolved, as written (e.g., "Foo" or "module.Foo"),
     /// and must be resolved using the registry's import information.
-    pub bases: Vec<String>,
+    pub bases: Vec<BaseClass>,
+    /// The guard(s) the class is defined under, if any (e.g. "if TYPE_CHECKING").
+    pub guard: Option<String>,
+    /// Whether the class is created dynamically (e.g. via `type(name, bases, ns)`).
+    pub is_dynamic: bool,
+    /// The metaclass declared by this class as written (e.g. "Meta"), if any.
+    pub metaclass: Option<String>,
+    /// The file the class is defined in.
+    ///
+    /// This may differ from the module's file if the module has both a source file and
+    /// a stub file.
+    pub file_path: PathBuf,
+    /// The location of the class definition.
+    ///
+    /// If the class is defined more than once, this is the location of the first definition.
+    pub location: Location,
+    /// The declared metaclass resolved to a class in the codebase, or failing that to an
+    /// external class (e.g. `abc.ABCMeta`), if possible.
+    ///
+    /// Populated once all modules are indexed. Note that this doesn't include
+    /// metaclasses inherited from parent classes.
+    pub resolved_metaclass: Option<ClassId>,
+    /// The methods and attributes defined in the class body.
+    pub members: Vec<ClassMember>,
+    /// The decorators applied to the class, as written (e.g. `@register("s3")`).
+    pub decorators: Vec<ClassDecorator>,
 }
 
 /// A unique identifier for a class within the codebase.
@@ -63,6 +196,14 @@ pub struct Registry {
     pub classes_by_module: HashMap<ModuleName, HashSet<ClassId>>,
     /// Import statements for each module.
     pub imports: HashMap<ModuleName, Vec<Import>>,
+    /// Import statements made inside functions, for each module.
+    pub local_imports: HashMap<ModuleName, Vec<LocalImport>>,
+    /// The names listed in `__all__`, for each module that defines it.
+    pub all_names: HashMap<ModuleName, Vec<String>>,
+    /// Module-level aliases (e.g. `Base = models.Model`) for each module.
+    pub aliases: HashMap<ModuleName, Vec<NameAlias>>,
+    /// Virtual subclass registrations (e.g. `Base.register(Foo)`) for each module.
+    pub registrations: HashMap<ModuleName, Vec<Registration>>,
 }
 
 impl Registry {
@@ -77,21 +218,37 @@ impl Registry {
     /// # Returns
     ///
     /// A fully constructed registry ready for class resolution.
+    ///
+    /// A module may be made up of several files (a source file and a stub file). In that
+    /// case their contents are merged, and classes defined in several files keep the file
+    /// path and location of the first definition.
     pub fn build(parsed_files: &[ParsedFile]) -> Result<Self> {
         let mut modules = HashMap::new();
         let mut classes = HashMap::new();
         let mut classes_by_module: HashMap<ModuleName, HashSet<ClassId>> = HashMap::new();
         let mut imports = HashMap::new();
+        let mut local_imports = HashMap::new();
+        let mut all_names = HashMap::new();
+        let mut aliases = HashMap::new();
+        let mut registrations = HashMap::new();
 
         for parsed in parsed_files {
             // Record module metadata
-            modules.insert(
-                parsed.module_path.clone(),
-                ModuleMetadata {
+            modules
+                .entry(parsed.module_path.clone())
+                .and_modify(|module: &mut ModuleMetadata| {
+                    module.is_package |= parsed.is_package;
+                })
+                .or_insert_with(|| ModuleMetadata {
                     file_path: parsed.file_path.clone(),
                     is_package: parsed.is_package,
-                },
-            );
+                    partial_files: Vec::new(),
+                });
+            if parsed.is_partial
+                && let Some(module) = modules.get_mut(&parsed.module_path)
+            {
+                module.partial_files.push(parsed.file_path.clone());
+            }
 
             // Index all class definitions from this module
             for class in &parsed.classes {
@@ -100,12 +257,69 @@ impl Registry {
                     name: class.name.clone(),
                 };
 
-                classes.insert(
-                    class_id.clone(),
-                    ClassMetadata {
-                        bases: class.bases.clone(),
-                    },
-                );
+                match classes.entry(class_id.clone()) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(ClassMetadata {
+                            bases: class.bases.clone(),
+                            guard: class.guard.clone(),
+                            is_dynamic: class.is_dynamic,
+                            metaclass: class.metaclass.clone(),
+                            file_path: class.file_path.clone(),
+                            location: class.location,
+                            resolved_metaclass: None,
+                            members: class.members.clone(),
+                            decorators: class.decorators.clone(),
+                        });
+                    }
+                    Entry::Occupied(mut entry) => {
+                        let existing = entry.get_mut();
+
+                        if existing.file_path == class.file_path
+                            && (class.guard.is_none() || class.guard == existing.guard)
+                        {
+                            // A redefinition in the same file which isn't guarded (or is
+                            // guarded like the earlier definitions) replaces them at runtime
+                            existing.location = class.location;
+                            existing.bases = class.bases.clone();
+                            existing.guard = class.guard.clone();
+                            existing.is_dynamic = class.is_dynamic;
+                            existing.metaclass = class.metaclass.clone();
+                            existing.members = class.members.clone();
+                            existing.decorators = class.decorators.clone();
+                        } else {
+                            // Otherwise either definition may be the one in effect (e.g. in
+                            // both branches of an `if`/`else`, or in a source file and its
+                            // stub), so merge the definitions
+                            for base in &class.bases {
+                                if !existing
+                                    .bases
+                                    .iter()
+                                    .any(|b| b.name == base.name && b.type_args == base.type_args)
+                                {
+                                    existing.bases.push(base.clone());
+                                }
+                            }
+                            if existing.metaclass.is_none() {
+                                existing.metaclass = class.metaclass.clone();
+                            }
+                            for decorator in &class.decorators {
+                                if !existing.decorators.contains(decorator) {
+                                    existing.decorators.push(decorator.clone());
+                                }
+                            }
+                            for member in &class.members {
+                                if !existing.members.iter().any(|m| m.name == member.name) {
+                                    existing.members.push(member.clone());
+                                }
+                            }
+                            existing.guard = match (existing.guard.take(), &class.guard) {
+                                (Some(a), Some(b)) if a != *b => Some(format!("{a} | {b}")),
+                                (Some(a), Some(_)) => Some(a),
+                                _ => None,
+                            };
+                        }
+                    }
+                }
 
                 classes_by_module
                     .entry(parsed.module_path.clone())
@@ -114,31 +328,270 @@ impl Registry {
             }
 
             // Store import statements for later resolution
-            imports.insert(parsed.module_path.clone(), parsed.imports.clone());
+            imports
+                .entry(parsed.module_path.clone())
+                .or_insert_with(Vec::new)
+                .extend(parsed.imports.iter().cloned());
+            local_imports
+                .entry(parsed.module_path.clone())
+                .or_insert_with(Vec::new)
+                .extend(parsed.local_imports.iter().cloned());
+
+            // Store `__all__` for expanding star imports
+            if let Some(names) = &parsed.all_names {
+                all_names
+                    .entry(parsed.module_path.clone())
+                    .or_insert_with(|| names.clone());
+            }
+
+            // Store module-level aliases for later resolution
+            aliases
+                .entry(parsed.module_path.clone())
+                .or_insert_with(Vec::new)
+                .extend(parsed.aliases.iter().cloned());
+
+            // Store virtual subclass registrations for later resolution
+            registrations
+                .entry(parsed.module_path.clone())
+                .or_insert_with(Vec::new)
+                .extend(parsed.registrations.iter().cloned());
         }
 
-        Ok(Self {
+        let mut registry = Self {
             modules,
             classes,
             classes_by_module,
             imports,
+            local_imports,
+            all_names,
+            aliases,
+            registrations,
+        };
+
+        // Resolve metaclasses, now that all modules are indexed
+        let resolved_metaclasses: Vec<(ClassId, ClassId)> = registry
+            .classes
+            .iter()
+            .filter_map(|(class_id, metadata)| {
+                let metaclass = metadata.metaclass.as_ref()?;
+                let resolved = registry
+                    .resolve_base_class(class_id, metaclass)
+                    .or_else(|| registry.resolve_external_class(class_id, metaclass))?;
+                Some((class_id.clone(), resolved))
+            })
+            .collect();
+        for (class_id, metaclass_id) in resolved_metaclasses {
+            if let Some(metadata) = registry.classes.get_mut(&class_id) {
+                metadata.resolved_metaclass = Some(metaclass_id);
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Resolves a base class name used in the definition of the class `class_id`.
+    ///
+    /// Local classes (classes defined inside a function, e.g. "make.<locals>.Handler")
+    /// can inherit from other local classes and from names imported in an enclosing
+    /// function, so those scopes are searched first, innermost first, before falling back
+    /// to [`Registry::resolve_class`].
+    ///
+    /// # Arguments
+    ///
+    /// * `class_id` - The class whose base is being resolved
+    /// * `name` - The base class name to resolve (may include dots for attribute access)
+    ///
+    /// # Returns
+    ///
+    /// The resolved `ClassId` if the class can be found, or `None` if resolution fails.
+    pub fn resolve_base_class(&self, class_id: &ClassId, name: &str) -> Option<ClassId> {
+        let mut scope = class_id.name.as_str();
+        while let Some(index) = scope.rfind(LOCALS) {
+            let local_scope = &scope[..index + LOCALS.len()];
+            let local_id = ClassId {
+                module: class_id.module.clone(),
+                name: format!("{local_scope}.{name}"),
+            };
+            if self.classes.contains_key(&local_id) {
+                return Some(local_id);
+            }
+            if let Some(import) = self.find_local_import(&class_id.module, local_scope, name) {
+                let qualified_name = format!(
+                    "{}{}",
+                    import.imported_item,
+                    &name[import.imported_as.len()..]
+                );
+                return self.resolve_qualified_name(&qualified_name, &mut HashSet::new());
+            }
+            scope = &scope[..index];
+        }
+
+        self.resolve_class(&class_id.module, name)
+    }
+
+    /// Qualifies a name used in the definition of the class `class_id` with the import it
+    /// refers to, if any.
+    ///
+    /// This is intended for names which don't resolve to a class in the codebase, such as
+    /// classes and decorators from the standard library or third-party packages. Names
+    /// which aren't imported are returned as is. Imports made in the functions enclosing
+    /// a local class take precedence over module-level imports.
+    ///
+    /// # Examples
+    ///
+    /// ```text
+    /// # In module "models":
+    /// from enum import Enum        # "Enum" => "enum.Enum"
+    /// import dataclasses as dc     # "dc.dataclass" => "dataclasses.dataclass"
+    /// import pydantic              # "pydantic.BaseModel" => "pydantic.BaseModel"
+    /// ```
+    pub fn qualify_imported_name(&self, class_id: &ClassId, name: &str) -> String {
+        match self.find_import(class_id, name) {
+            Some(import) => format!(
+                "{}{}",
+                import.imported_item,
+                &name[import.imported_as.len()..]
+            ),
+            None => name.to_string(),
+        }
+    }
+
+    /// Resolves a base class name used in the definition of the class `class_id` to an
+    /// external class, i.e. a class from the standard library or a third-party package.
+    ///
+    /// The name is qualified by the import it refers to (following module-level
+    /// aliases), e.g. `models.Model` after `from django.db import models` resolves to
+    /// `ClassId { module: "django.db.models", name: "Model" }`. Builtin classes which
+    /// aren't imported (e.g. `Exception`) are resolved in the `builtins` module.
+    ///
+    /// This is intended for names which [`Registry::resolve_base_class`] can't resolve.
+    ///
+    /// # Returns
+    ///
+    /// The external `ClassId`, or `None` if the name isn't imported (and isn't a builtin
+    /// class), or is imported from a module of the codebase.
+    pub fn resolve_external_class(&self, class_id: &ClassId, name: &str) -> Option<ClassId> {
+        let module = class_id.module.as_str();
+
+        // Follow module-level aliases, e.g. `Base = models.Model`
+        let mut name = name.to_string();
+        let mut visited = HashSet::new();
+        while visited.insert(name.clone())
+            && let Some(aliased_name) =
+                self.aliases
+                    .get(module)
+                    .into_iter()
+                    .flatten()
+                    .find_map(|alias| {
+                        if name == alias.name {
+                            Some(alias.target.clone())
+                        } else {
+                            name.strip_prefix(&format!("{}.", alias.name))
+                                .map(|remainder| format!("{}.{}", alias.target, remainder))
+                        }
+                    })
+        {
+            name = aliased_name;
+        }
+
+        let qualified_name = match self.find_import(class_id, &name) {
+            Some(import) => format!(
+                "{}{}",
+                import.imported_item,
+                &name[import.imported_as.len()..]
+            ),
+            None if is_builtin_class(&name) => format!("builtins.{name}"),
+            None => return None,
+        };
+
+        // Names imported from the codebase which can't be resolved aren't external
+        if qualified_name
+            .match_indices('.')
+            .any(|(index, _)| self.modules.contains_key(&qualified_name[..index]))
+        {
+            return None;
+        }
+
+        let (external_module, external_name) = qualified_name.rsplit_once('.')?;
+        Some(ClassId {
+            module: external_module.to_string(),
+            name: external_name.to_string(),
         })
     }
 
+    /// Returns the import that a (possibly dotted) name used in the definition of the
+    /// class `class_id` refers to, if any.
+    ///
+    /// The imports of the functions enclosing a local class are searched first, innermost
+    /// first, before the module-level imports.
+    fn find_import(&self, class_id: &ClassId, name: &str) -> Option<&Import> {
+        let mut scope = class_id.name.as_str();
+        while let Some(index) = scope.rfind(LOCALS) {
+            let local_scope = &scope[..index + LOCALS.len()];
+            if let Some(import) = self.find_local_import(&class_id.module, local_scope, name) {
+                return Some(import);
+            }
+            scope = &scope[..index];
+        }
+
+        longest_matching_import(
+            self.imports.get(&class_id.module).into_iter().flatten(),
+            name,
+        )
+    }
+
+    /// Returns the import made in the function scope `local_scope` of `module` that a
+    /// (possibly dotted) name refers to, if any.
+    fn find_local_import(&self, module: &str, local_scope: &str, name: &str) -> Option<&Import> {
+        let imports = self
+            .local_imports
+            .get(module)
+            .into_iter()
+            .flatten()
+            .filter(|local_import| local_import.scope == local_scope)
+            .map(|local_import| &local_import.import);
+        longest_matching_import(imports, name)
+    }
+
+    /// Resolves both sides of a virtual subclass registration made in `module`.
+    ///
+    /// # Returns
+    ///
+    /// The resolved `(base, subclass)` pair, or `None` if either side can't be resolved.
+    pub fn resolve_registration(
+        &self,
+        module: &str,
+        registration: &Registration,
+    ) -> Option<(ClassId, ClassId)> {
+        let subclass_id = self.resolve_class(module, &registration.subclass)?;
+        // Resolve the base in the scope the registration is made in
+        let scope = ClassId {
+            module: module.to_string(),
+            name: registration.subclass.clone(),
+        };
+        let base_id = self.resolve_base_class(&scope, &registration.base)?;
+        Some((base_id, subclass_id))
+    }
+
     /// Resolves a class name within a given module's context.
     ///
     /// This method handles the complexity of Python's import system, including:
     /// - Direct class references within the same module
     /// - Classes imported from other modules
     /// - Classes re-exported through `__init__.py` files
+    /// - Classes re-exported through star imports (`from .models import *`),
+    ///   honouring the source module's `__all__`
     /// - Attribute-style class references (e.g., "module.Class")
+    /// - Module-level aliases (e.g., `Base = models.Model`)
     ///
     /// # Algorithm
     ///
     /// 1. Check if the class exists directly in the specified module
+    ///    (following module-level aliases)
     /// 2. If not, consult the module's imports to resolve the name:
     ///    - Match against `imported_as` names from import statements
     ///    - Substitute with the actual `imported_item` path
+    ///    - If no import matches, look the name up in star-imported modules
     /// 3. Parse the resolved name to find the defining module:
     ///    - Try progressively shorter prefixes (e.g., "a.b.c" → "a.b" → "a")
     ///    - Stop when we find a module that exists
@@ -162,6 +615,23 @@ impl Registry {
     ///     pass
     /// ```
     pub fn resolve_class(&self, module: &str, name: &str) -> Option<ClassId> {
+        self.resolve_class_inner(module, name, &mut HashSet::new())
+    }
+
+    /// Implementation of [`Registry::resolve_class`].
+    ///
+    /// `visited` tracks the (module, name) pairs already being resolved, so that
+    /// import cycles (e.g. two modules star-importing each other) terminate.
+    fn resolve_class_inner(
+        &self,
+        module: &str,
+        name: &str,
+        visited: &mut HashSet<(ModuleName, String)>,
+    ) -> Option<ClassId> {
+        if !visited.insert((module.to_string(), name.to_string())) {
+            return None;
+        }
+
         // First, check for a direct class definition in this module
         let direct_id = ClassId {
             module: module.to_string(),
@@ -171,26 +641,74 @@ impl Registry {
             return Some(direct_id);
         }
 
+        // Follow module-level aliases
+        // Example: If the module contains `Base = models.Model`,
+        // then "Base" is resolved as "models.Model" in the same module
+        if let Some(aliases) = self.aliases.get(module) {
+            for alias in aliases {
+                let aliased_name = if name == alias.name {
+                    alias.target.clone()
+                } else if let Some(remainder) = name.strip_prefix(&format!("{}.", alias.name)) {
+                    format!("{}.{}", alias.target, remainder)
+                } else {
+                    continue;
+                };
+                if let Some(class_id) = self.resolve_class_inner(module, &aliased_name, visited) {
+                    return Some(class_id);
+                }
+            }
+        }
+
         // Not found directly - use imports to resolve the reference
         let imports = self.imports.get(module)?;
 
         // Substitute imported names with their actual module paths
         // Example: If "Dog" is imported as "from animals import Dog",
         // then "Dog" becomes "animals.Dog"
-        let mut resolved_name = name.to_string();
+        let mut resolved_name = None;
 
         for import in imports {
             if name == import.imported_as {
                 // Exact match: "Dog" → "animals.Dog"
-                resolved_name = import.imported_item.clone();
+                resolved_name = Some(import.imported_item.clone());
                 break;
             } else if let Some(remainder) = name.strip_prefix(&format!("{}.", import.imported_as)) {
                 // Prefix match: "Dog.Puppy" → "animals.Dog.Puppy"
-                resolved_name = format!("{}.{}", import.imported_item, remainder);
+                resolved_name = Some(format!("{}.{}", import.imported_item, remainder));
                 break;
             }
         }
 
+        // No explicit import matched - try the modules star-imported into this module
+        // Example: If "Dog" is not imported explicitly but `from animals import *` is,
+        // then look for "Dog" in "animals"
+        if resolved_name.is_none() {
+            let first_part = name.split('.').next().unwrap_or(name);
+            for import in imports {
+                if import.imported_as != "*" {
+                    continue;
+                }
+                let Some(source_module) = import.imported_item.strip_suffix(".*") else {
+                    continue;
+                };
+                if self.exports_name(source_module, first_part)
+                    && let Some(class_id) = self.resolve_class_inner(source_module, name, visited)
+                {
+                    return Some(class_id);
+                }
+            }
+        }
+
+        let resolved_name = resolved_name.unwrap_or_else(|| name.to_string());
+        self.resolve_qualified_name(&resolved_name, visited)
+    }
+
+    /// Resolves a name qualified by the module defining it (e.g. "animals.Dog").
+    fn resolve_qualified_name(
+        &self,
+        resolved_name: &str,
+        visited: &mut HashSet<(ModuleName, String)>,
+    ) -> Option<ClassId> {
         // Parse the resolved name to find the defining module and class
         // Example: "animals.Dog" needs to be split into module "animals" and class "Dog"
         let parts: Vec<&str> = resolved_name.split('.').collect();
@@ -210,11 +728,53 @@ impl Registry {
                 let remainder = parts[i..].join(".");
 
                 // Recursively resolve in case the class itself is re-exported
-                return self.resolve_class(&module_candidate, &remainder);
+                return self.resolve_class_inner(&module_candidate, &remainder, visited);
             }
         }
 
         // Unable to resolve this class reference
         None
     }
+
+    /// Returns whether `from module import *` would bind `name`.
+    ///
+    /// If the module defines `__all__`, only the names listed there are exported.
+    /// Otherwise all public names (names not starting with an underscore) are.
+    fn exports_name(&self, module: &str, name: &str) -> bool {
+        if !self.modules.contains_key(module) {
+            return false;
+        }
+        match self.all_names.get(module) {
+            Some(all_names) => all_names.iter().any(|n| n == name),
+            None => !name.starts_with('_'),
+        }
+    }
+}
+
+/// Returns whether a name is a class of the `builtins` module (e.g. "Exception").
+fn is_builtin_class(name: &str) -> bool {
+    BUILTIN_CLASSES.contains(&name) || BUILTIN_EXCEPTIONS.contains(&name)
+}
+
+/// Returns whether a class is a builtin exception class (e.g. `builtins.ValueError`).
+pub fn is_builtin_exception(id: &ClassId) -> bool {
+    id.module == "builtins" && BUILTIN_EXCEPTIONS.contains(&id.name.as_str())
+}
+
+/// Returns the import among `imports` that a (possibly dotted) name refers to, if any.
+///
+/// If several imports match (e.g. `import a` and `import a.b` for "a.b.C"), the one
+/// binding the longest name wins.
+fn longest_matching_import<'a>(
+    imports: impl Iterator<Item = &'a Import>,
+    name: &str,
+) -> Option<&'a Import> {
+    imports
+        .filter(|import| {
+            name == import.imported_as
+                || name
+                    .strip_prefix(import.imported_as.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+        .max_by_key(|import| import.imported_as.len())
 }
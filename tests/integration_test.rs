@@ -953,6 +953,97 @@ class Dog(Animal, Walker):
     temp.close().unwrap();
 }
 
+#[test]
+fn test_class_tags_on_request() {
+    use pysubclasses::{ClassTag, SearchMode, SearchOptions, SubclassFinder};
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("colors.py")
+        .write_str(
+            r#"
+import enum
+
+class Base:
+    pass
+
+class Color(Base, enum.Enum):
+    RED = 1
+"#,
+        )
+        .unwrap();
+
+    let finder = SubclassFinder::new(temp.path().to_path_buf()).unwrap();
+
+    // Classes are only classified when asked to
+    let subclasses = finder
+        .find_subclasses_with_options("Base", None, SearchMode::All, &SearchOptions::default())
+        .unwrap();
+    assert_eq!(subclasses.len(), 1);
+    assert!(subclasses[0].tags.is_empty());
+    assert!(subclasses[0].enum_members.is_empty());
+
+    let options = SearchOptions {
+        include_tags: true,
+        include_enum_members: true,
+        ..SearchOptions::default()
+    };
+    let subclasses = finder
+        .find_subclasses_with_options("Base", None, SearchMode::All, &options)
+        .unwrap();
+    assert_eq!(subclasses[0].tags, vec![ClassTag::Concrete, ClassTag::Enum]);
+    assert_eq!(subclasses[0].enum_members.len(), 1);
+    assert_eq!(subclasses[0].enum_members[0].name, "RED");
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_find_parent_classes_external() {
+    use pysubclasses::{SearchMode, SubclassFinder};
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("errors.py")
+        .write_str(
+            r#"
+from django.db import models
+
+class AppError(Exception):
+    pass
+
+class ModelError(AppError, models.Model):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let finder = SubclassFinder::new(temp.path().to_path_buf()).unwrap();
+
+    // External classes are left out by default
+    let parents = finder
+        .find_parent_classes("ModelError", Some("errors"), SearchMode::All)
+        .unwrap();
+    assert_eq!(parents.len(), 1);
+    assert_eq!(parents[0].class_name, "AppError");
+
+    let parents = finder
+        .find_parent_classes_with_external("ModelError", Some("errors"), SearchMode::All)
+        .unwrap();
+    let parent_names: Vec<_> = parents.iter().map(|p| p.qualified_name()).collect();
+    assert_eq!(parents.len(), 3);
+    assert!(parent_names.contains(&"errors.AppError".to_string()));
+    assert!(parent_names.contains(&"builtins.Exception".to_string()));
+    assert!(parent_names.contains(&"django.db.models.Model".to_string()));
+    assert!(
+        parents
+            .iter()
+            .all(|p| p.is_external == (p.module_path != "errors"))
+    );
+
+    temp.close().unwrap();
+}
+
 #[test]
 fn test_module_as_relative_file_path() {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -1098,3 +1189,2055 @@ fn test_module_dotted_path_still_works() {
 
     temp.close().unwrap();
 }
+
+#[test]
+fn test_conditionally_defined_classes() {
+    use pysubclasses::SubclassFinder;
+
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    temp.child("compat.py")
+        .write_str(
+            r#"
+from typing import TYPE_CHECKING
+from base import Animal
+
+if TYPE_CHECKING:
+    class Dog(Animal):
+        pass
+
+try:
+    from fast import Cat
+except ImportError:
+    class Cat(Animal):
+        pass
+
+if PY2:
+    class Bird(Animal):
+        pass
+else:
+    class Bird(object):
+        pass
+
+class Pet(Animal):
+    pass
+
+# An unconditional redefinition replaces the earlier definition
+class Pet:
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (compat) compat.py:6:5"))
+        .stdout(predicate::str::contains("Cat (compat) compat.py:12:5"))
+        .stdout(predicate::str::contains("Bird (compat)"))
+        .stdout(predicate::str::contains("Pet").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--show-guards")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (compat) [if TYPE_CHECKING]"))
+        .stdout(predicate::str::contains(
+            "Cat (compat) [except ImportError]",
+        ))
+        .stdout(predicate::str::contains("Bird (compat) [if PY2 | else]"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"guard\": \"if TYPE_CHECKING\""));
+
+    // The redefinition of Pet is reported where it is defined
+    let finder = SubclassFinder::new(temp.path().to_path_buf()).unwrap();
+    let pet = finder
+        .resolve_class_reference("Pet", Some("compat"))
+        .unwrap();
+    assert_eq!(pet.location.line, 26);
+    assert!(pet.bases.is_empty());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_local_classes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    temp.child("factory.py")
+        .write_str(
+            r#"
+from base import Animal
+
+class Dog(Animal):
+    pass
+
+def make_animal():
+    class Cat(Animal):
+        pass
+
+    class Kitten(Cat):
+        pass
+
+    return Kitten
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (factory)"))
+        .stdout(predicate::str::contains(
+            "make_animal.<locals>.Cat (factory)",
+        ))
+        .stdout(predicate::str::contains(
+            "make_animal.<locals>.Kitten (factory)",
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--no-local")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (factory)"))
+        .stdout(predicate::str::contains("<locals>").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_local_imports() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("a.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+    temp.child("b.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+
+    // The import inside the function only shadows `Model` within the function
+    temp.child("app.py")
+        .write_str(
+            r#"
+from a import Model
+
+class Top(Model):
+    pass
+
+def make_model():
+    from b import Model
+
+    class Local(Model):
+        pass
+
+    return Local
+
+class Bottom(Model):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--module")
+        .arg("a")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Top (app)"))
+        .stdout(predicate::str::contains("Bottom (app)"))
+        .stdout(predicate::str::contains("Local").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--module")
+        .arg("b")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("make_model.<locals>.Local (app)"))
+        .stdout(predicate::str::contains("Top").not())
+        .stdout(predicate::str::contains("Bottom").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_star_import_reexport() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    // pkg re-exports everything from pkg.sub, which re-exports pkg.sub.models
+    temp.child("pkg/__init__.py")
+        .write_str("from .sub import *\n")
+        .unwrap();
+    temp.child("pkg/sub/__init__.py")
+        .write_str("from .models import *\n")
+        .unwrap();
+    temp.child("pkg/sub/models.py")
+        .write_str(
+            r#"
+__all__ = ["Animal"]
+__all__ += ["Plant"]
+
+class Animal:
+    pass
+
+class Plant:
+    pass
+
+class Hidden:
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("zoo.py")
+        .write_str(
+            r#"
+from pkg import *
+
+class Dog(Animal):
+    pass
+
+class Rose(Plant):
+    pass
+
+class Ghost(Hidden):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (zoo)"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Plant")
+        .arg("--module")
+        .arg("pkg")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Rose (zoo)"));
+
+    // `Hidden` is not listed in `__all__`, so it isn't star-imported
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Hidden")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No subclasses found"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_star_import_cycle() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("a.py")
+        .write_str("from b import *\n\nclass Animal:\n    pass\n")
+        .unwrap();
+    temp.child("b.py")
+        .write_str("from a import *\n\nclass Dog(Missing):\n    pass\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No subclasses found"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_module_level_aliases() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+
+    temp.child("app.py")
+        .write_str(
+            r#"
+import models
+from typing import TypeAlias
+
+Base = models.Model
+Annotated: TypeAlias = Base
+type Modern = models.Model
+
+# Cyclic aliases must not hang resolution
+A = B
+B = A
+
+class User(Base):
+    pass
+
+class Group(Annotated):
+    pass
+
+class Team(Modern):
+    pass
+
+class Broken(A):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("User (app)"))
+        .stdout(predicate::str::contains("Group (app)"))
+        .stdout(predicate::str::contains("Team (app)"))
+        .stdout(predicate::str::contains("Broken").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_dynamic_classes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("animals.py")
+        .write_str(
+            r#"
+import types
+
+class Animal:
+    pass
+
+Dog = type("Dog", (Animal,), {"sound": "woof"})
+Puppy = types.new_class("Puppy", (Dog,))
+
+class Husky(Puppy):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"class_name\": \"Dog\""))
+        .stdout(predicate::str::contains("\"class_name\": \"Puppy\""))
+        .stdout(predicate::str::contains("\"class_name\": \"Husky\""))
+        .stdout(predicate::str::contains("\"is_dynamic\": true"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Dog")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--mode")
+        .arg("direct")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Puppy (animals)"))
+        .stdout(predicate::str::contains("Husky").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_base_forwarding_calls() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("animals.py")
+        .write_str(
+            r#"
+import six
+from compat import with_base
+
+class Meta(type):
+    pass
+
+class Animal:
+    pass
+
+class Dog(six.with_metaclass(Meta, Animal)):
+    pass
+
+class Cat(with_base(Animal)):
+    pass
+"#,
+        )
+        .unwrap();
+
+    // `with_metaclass` is always recognised, custom helpers only when configured
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (animals)"))
+        .stdout(predicate::str::contains("Cat").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--base-forwarding-call")
+        .arg("with_base")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (animals)"))
+        .stdout(predicate::str::contains("Cat (animals)"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_metaclass_users() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("meta.py")
+        .write_str(
+            r#"
+class Meta(type):
+    pass
+
+class SubMeta(Meta):
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("models.py")
+        .write_str(
+            r#"
+import six
+from meta import Meta, SubMeta
+
+class Model(metaclass=Meta):
+    pass
+
+class User(Model):
+    pass
+
+class Legacy(six.with_metaclass(SubMeta, object)):
+    pass
+
+class Plain:
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Meta")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--metaclass")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 3 class(es) using metaclass 'Meta'",
+        ))
+        .stdout(predicate::str::contains("Model (models)"))
+        .stdout(predicate::str::contains("User (models)"))
+        .stdout(predicate::str::contains("Legacy (models)"))
+        .stdout(predicate::str::contains("Plain").not());
+
+    // Metaclasses which aren't defined in the codebase can be searched for too
+    temp.child("shapes.py")
+        .write_str(
+            r#"
+import abc
+
+class Shape(metaclass=abc.ABCMeta):
+    pass
+
+class Square(Shape):
+    pass
+
+class PluginMeta(abc.ABCMeta):
+    pass
+
+class Plugin(metaclass=PluginMeta):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("ABCMeta")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--metaclass")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 3 class(es) using metaclass 'ABCMeta'",
+        ))
+        .stdout(predicate::str::contains("Shape (shapes)"))
+        .stdout(predicate::str::contains("Square (shapes)"))
+        .stdout(predicate::str::contains("Plugin (shapes)"))
+        .stdout(predicate::str::contains("Model").not());
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_type_arguments() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str(
+            r#"
+class User:
+    pass
+
+class Group:
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("repos.py")
+        .write_str(
+            r#"
+from typing import Generic, TypeVar
+from models import User, Group
+
+T = TypeVar("T")
+
+class Repository(Generic[T]):
+    pass
+
+class UserRepo(Repository[User]):
+    pass
+
+class AdminRepo(UserRepo):
+    pass
+
+class GroupRepo(Repository["Group"]):
+    pass
+"#,
+        )
+        .unwrap();
+
+    // The specialisation is shown, with type arguments resolved through imports
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Repository")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "UserRepo (repos): Repository[models.User]",
+        ))
+        .stdout(predicate::str::contains(
+            "GroupRepo (repos): Repository[models.Group]",
+        ))
+        .stdout(predicate::str::contains("AdminRepo (repos) repos.py:13:1"));
+
+    // Filtering by type argument includes subclasses of matching classes
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Repository")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--type-arg")
+        .arg("User")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 2 subclass(es)"))
+        .stdout(predicate::str::contains("UserRepo (repos)"))
+        .stdout(predicate::str::contains("AdminRepo (repos)"))
+        .stdout(predicate::str::contains("GroupRepo").not());
+
+    // Type arguments are included in the JSON output
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Repository")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"name\": \"repos.Repository\""))
+        .stdout(predicate::str::contains("\"models.User\""));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_source_locations() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    temp.child("pets/dogs.py")
+        .write_str("from base import Animal\n\n\nclass Dog(\n    Animal,\n):\n    pass\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (pets.dogs) pets/dogs.py:4:1"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"line\": 4"))
+        .stdout(predicate::str::contains("\"end_line\": 7"))
+        .stdout(predicate::str::contains("\"line\": 5"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("dot")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tooltip=\"pets/dogs.py:4:1\""));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_stub_files() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    // A compiled extension module, only described by its stub
+    temp.child("fast.pyi")
+        .write_str("from base import Animal\n\nclass Cheetah(Animal): ...\n")
+        .unwrap();
+
+    // A module with both a source file and a (partial) stub
+    temp.child("pets/__init__.py")
+        .write_str(
+            "from base import Animal\n\nclass Dog(Animal):\n    pass\n\nclass Cat(Animal):\n    pass\n",
+        )
+        .unwrap();
+    temp.child("pets/__init__.pyi")
+        .write_str("from base import Animal\n\nclass Dog(Animal): ...\n")
+        .unwrap();
+
+    // By default both files are merged, classes defined in both come from the stub
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cheetah (fast) fast.pyi:3:1"))
+        .stdout(predicate::str::contains("Dog (pets) pets/__init__.pyi:3:1"))
+        .stdout(predicate::str::contains("Cat (pets) pets/__init__.py:6:1"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--stubs")
+        .arg("prefer-stub")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cheetah (fast)"))
+        .stdout(predicate::str::contains("Dog (pets) pets/__init__.pyi:3:1"))
+        .stdout(predicate::str::contains("Cat").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--stubs")
+        .arg("prefer-source")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cheetah (fast)"))
+        .stdout(predicate::str::contains("Dog (pets) pets/__init__.py:3:1"))
+        .stdout(predicate::str::contains("Cat (pets) pets/__init__.py:6:1"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_notebooks() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+
+    temp.child("analysis.ipynb")
+        .write_str(
+            r###"{
+ "cells": [
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["!pip install pandas\n", "from models import Model"]},
+  {"cell_type": "markdown", "metadata": {}, "source": ["## Custom models"]},
+  {"cell_type": "code", "metadata": {}, "outputs": [], "source": ["class Forecast(Model):\n", "    pass\n"]}
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"###,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Forecast (analysis) analysis.ipynb:cell2:1:1",
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"cell\": 2"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_source_encodings() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    temp.child("legacy.py")
+        .write_binary(b"# -*- coding: latin-1 -*-\nfrom base import Animal\n\nclass Caf\xE9Cat(Animal):\n    pass\n")
+        .unwrap();
+
+    temp.child("bom.py")
+        .write_binary(b"\xEF\xBB\xBFfrom base import Animal\n\nclass Dog(Animal):\n    pass\n")
+        .unwrap();
+
+    // Undecodable files are skipped with a decode warning
+    temp.child("broken.py")
+        .write_binary(b"from base import Animal\n\nclass Cow(Animal):\n    pass\n# caf\xE9\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .env("RUST_LOG", "warn")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("CaféCat (legacy)"))
+        .stdout(predicate::str::contains("Dog (bom)"))
+        .stdout(predicate::str::contains("Cow").not())
+        .stderr(predicate::str::contains("Failed to decode"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_error_tolerant_parsing() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("base.py")
+        .write_str("class Animal:\n    pass\n")
+        .unwrap();
+
+    temp.child("pets.py")
+        .write_str(
+            r#"
+from base import Animal
+
+class Dog(Animal):
+    pass
+
+def unfinished(
+"#,
+        )
+        .unwrap();
+
+    // By default, files with syntax errors are skipped
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No subclasses found"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--error-tolerant")
+        .env("RUST_LOG", "warn")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Dog (pets)"))
+        .stderr(predicate::str::contains(
+            "pets.py has syntax errors and was only partially parsed",
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Animal")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--error-tolerant")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\"is_partial\": true"))
+        .stdout(predicate::str::contains("\"partially_parsed_files\": ["));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_virtual_subclasses() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("shapes.py")
+        .write_str(
+            r#"
+import abc
+
+class Shape(abc.ABC):
+    pass
+
+class Square(Shape):
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("legacy.py")
+        .write_str(
+            r#"
+from shapes import Shape
+
+class Circle:
+    pass
+
+class Ellipse(Circle):
+    pass
+
+@Shape.register
+class Triangle:
+    pass
+
+Shape.register(Circle)
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Shape")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--include-virtual")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 4 subclass(es)"))
+        .stdout(predicate::str::contains("Square (shapes) shapes.py"))
+        .stdout(predicate::str::contains("Circle (legacy) [virtual]"))
+        .stdout(predicate::str::contains("Ellipse (legacy) [virtual]"))
+        .stdout(predicate::str::contains("Triangle (legacy) [virtual]"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Shape")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 subclass(es)"))
+        .stdout(predicate::str::contains("Square (shapes)"))
+        .stdout(predicate::str::contains("[virtual]").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Shape")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--include-virtual")
+        .arg("--format")
+        .arg("dot")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "shapes_Shape -> legacy_Circle [style=dotted, label=\"register\"];",
+        ))
+        .stdout(predicate::str::contains("legacy_Circle -> legacy_Ellipse;"));
+
+    temp.close().unwrap();
+}
+
+#[test]
+fn test_protocol_implementations() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("protocols.py")
+        .write_str(
+            r#"
+from typing import Protocol
+
+class SupportsClose(Protocol):
+    def close(self) -> None: ...
+
+class Resource(SupportsClose, Protocol):
+    name: str
+
+    def open(self) -> None: ...
+
+class Marker(Protocol):
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("resources.py")
+        .write_str(
+            r#"
+import asyncio
+
+class Base:
+    def close(self):
+        pass
+
+class Echo(asyncio.Protocol):
+    def close(self):
+        pass
+
+class File(Base):
+    name = "file"
+
+    def open(self):
+        pass
+
+class Socket:
+    name = "socket"
+
+    def open(self):
+        pass
+
+class Pipe(File):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Resource")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--protocol")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 2 implementation(s) of protocol 'Resource'",
+        ))
+        .stdout(predicate::str::contains("File (resources)"))
+        .stdout(predicate::str::contains("Pipe (resources)"))
+        .stdout(predicate::str::contains("Socket").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("SupportsClose")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--protocol")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 4 implementation(s)"))
+        .stdout(predicate::str::contains("Base (resources)"))
+        // `asyncio.Protocol` isn't `typing.Protocol`
+        .stdout(predicate::str::contains("Echo (resources)"))
+        .stdout(predicate::str::contains("Resource").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Resource")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--protocol")
+        .arg("--format")
+        .arg("dot")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "protocols_Resource -> resources_File [style=dashed, label=\"implements\"];",
+        ))
+        .stdout(predicate::str::contains(
+            "resources_File -> resources_Pipe;",
+        ));
+
+    // Classes which aren't protocols, and protocols without members, are rejected
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--protocol")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Class 'resources.Base' is not a protocol",
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Marker")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--protocol")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Protocol 'protocols.Marker' has no members to implement",
+        ));
+}
+
+#[test]
+fn test_member_status() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str(
+            r#"
+class Model:
+    def save(self):
+        pass
+
+class User(Model):
+    table = "users"
+
+    def save(self):
+        pass
+
+class Admin(User):
+    pass
+
+class Group(Model):
+    @property
+    def table(self):
+        return "groups"
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--member")
+        .arg("save")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 3 subclass(es)"))
+        .stdout(predicate::str::contains("User (models) [overrides save]"))
+        .stdout(predicate::str::contains("Admin (models) [inherits save]"))
+        .stdout(predicate::str::contains("Group (models) [inherits save]"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--member")
+        .arg("save")
+        .arg("--member-status")
+        .arg("overrides")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 subclass(es)"))
+        .stdout(predicate::str::contains("User (models) [overrides save]"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--member")
+        .arg("table")
+        .arg("--member-status")
+        .arg("defines,missing")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""member_status": "defines""#))
+        .stdout(predicate::str::contains(r#""member_status": "inherits""#).not())
+        .stdout(predicate::str::contains(r#""class_name": "Admin""#).not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--member-status")
+        .arg("overrides")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_check_abstract() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("shapes.py")
+        .write_str(
+            r#"
+import abc
+
+class Shape(abc.ABC):
+    @abc.abstractmethod
+    def area(self): ...
+
+    @abc.abstractmethod
+    def perimeter(self): ...
+
+class AreaMixin:
+    def area(self):
+        return 0
+
+class Square(Shape):
+    def area(self): ...
+    def perimeter(self): ...
+
+class Circle(Shape):
+    def area(self): ...
+
+class Blob(Shape, AreaMixin):
+    pass
+
+class Blob2(AreaMixin, Shape):
+    def perimeter(self): ...
+
+class RoundedShape(Shape):
+    @abc.abstractmethod
+    def radius(self): ...
+
+class Ring(RoundedShape):
+    def area(self): ...
+    def perimeter(self): ...
+
+class Left(Shape):
+    def area(self): ...
+
+class Right(Shape):
+    def perimeter(self): ...
+
+class Both(Right, Left):
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("plugins.py")
+        .write_str(
+            r#"
+from abc import ABC, abstractmethod
+
+class Plugin(ABC):
+    @abstractmethod
+    def run(self): ...
+
+class Echo(Plugin):
+    def run(self): ...
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Shape")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--check-abstract")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            "Found 5 subclass(es) of 'Shape' with unimplemented abstract methods",
+        ))
+        .stdout(predicate::str::contains(
+            "Circle (shapes) [unimplemented: perimeter]",
+        ))
+        .stdout(predicate::str::contains(
+            "Blob (shapes) [unimplemented: area, perimeter]",
+        ))
+        .stdout(predicate::str::contains(
+            "Ring (shapes) [unimplemented: radius]",
+        ))
+        .stdout(predicate::str::contains(
+            "Left (shapes) [unimplemented: perimeter]",
+        ))
+        .stdout(predicate::str::contains(
+            "Right (shapes) [unimplemented: area]",
+        ))
+        .stdout(predicate::str::contains("Square").not())
+        .stdout(predicate::str::contains("Blob2").not())
+        .stdout(predicate::str::contains("RoundedShape").not())
+        .stdout(predicate::str::contains("Both").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Shape")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--check-abstract")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains(
+            r#""declared_in": "shapes.RoundedShape""#,
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Plugin")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--check-abstract")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "All subclasses of 'Plugin' implement its abstract methods",
+        ));
+}
+
+#[test]
+fn test_class_tags() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str(
+            r#"
+import abc
+import enum
+from dataclasses import dataclass
+import attr
+from pydantic import BaseModel
+from requests import HTTPError
+import ormlib
+
+class Base:
+    pass
+
+class AbstractThing(Base, abc.ABC):
+    @abc.abstractmethod
+    def run(self): ...
+
+class Thing(AbstractThing):
+    def run(self): ...
+
+class AuditMixin(Base):
+    pass
+
+class Color(Base, enum.Enum):
+    RED = 1
+
+@dataclass
+class Point(Base):
+    x: int
+
+class Point3D(Point):
+    z: int
+
+@attr.s
+class Legacy(Base):
+    pass
+
+class Settings(Base, BaseModel):
+    pass
+
+class Failure(Base, ValueError):
+    pass
+
+class RetryFailure(Failure):
+    pass
+
+class ApiError(Base, HTTPError):
+    pass
+
+class Record(Base, ormlib.BaseModel):
+    pass
+
+class Node(Base, ormlib.ABC):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--only")
+        .arg("concrete")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 11 subclass(es)"))
+        .stdout(predicate::str::contains("  Thing (models)"))
+        // Only `abc.ABC` makes a class abstract
+        .stdout(predicate::str::contains("Node (models)"))
+        .stdout(predicate::str::contains("AbstractThing").not())
+        .stdout(predicate::str::contains("AuditMixin").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--only")
+        .arg("enum,exception")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 3 subclass(es)"))
+        .stdout(predicate::str::contains("Color (models)"))
+        .stdout(predicate::str::contains("  Failure (models)"))
+        .stdout(predicate::str::contains("RetryFailure (models)"))
+        // Exceptions are recognised through their `BaseException` ancestry, not their name
+        .stdout(predicate::str::contains("ApiError").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--only")
+        .arg("dataclass")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 4 subclass(es)"))
+        .stdout(predicate::str::contains("Point (models)"))
+        .stdout(predicate::str::contains("Point3D (models)"))
+        .stdout(predicate::str::contains("Legacy (models)"))
+        .stdout(predicate::str::contains("Settings (models)"))
+        // Only `pydantic.BaseModel` makes a class a model
+        .stdout(predicate::str::contains("Record").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--only")
+        .arg("abstract,mixin")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""class_name": "AbstractThing""#))
+        .stdout(predicate::str::contains(r#""class_name": "AuditMixin""#))
+        .stdout(predicate::str::contains(r#""abstract""#))
+        .stdout(predicate::str::contains(r#""mixin""#))
+        .stdout(predicate::str::contains(r#""concrete""#).not());
+}
+
+#[test]
+fn test_decorated_subclasses() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("plugins.py")
+        .write_str(
+            r#"
+def register_backend(name, **options):
+    def register(cls):
+        return cls
+    return register
+
+class Backend:
+    pass
+"#,
+        )
+        .unwrap();
+
+    temp.child("backends.py")
+        .write_str(
+            r#"
+import plugins
+from plugins import Backend, register_backend
+
+GCS_NAME = "gcs"
+
+@register_backend("s3", priority=10)
+class S3Backend(Backend):
+    pass
+
+@plugins.register_backend(GCS_NAME, "google")
+class GcsBackend(Backend):
+    pass
+
+class LocalBackend(Backend):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Backend")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--decorated-with")
+        .arg("register_backend")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 2 subclass(es)"))
+        .stdout(predicate::str::contains(
+            r#"S3Backend (backends) [@plugins.register_backend("s3", priority=10)]"#,
+        ))
+        .stdout(predicate::str::contains(
+            r#"GcsBackend (backends) [@plugins.register_backend(GCS_NAME, "google")]"#,
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Backend")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--not-decorated-with")
+        .arg("register_backend")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 subclass(es)"))
+        .stdout(predicate::str::contains("LocalBackend (backends)"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Backend")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""name": "plugins.register_backend""#,
+        ))
+        .stdout(predicate::str::contains(r#""priority": 10"#))
+        .stdout(predicate::str::contains(r#""expr": "GCS_NAME""#));
+}
+
+#[test]
+fn test_attribute_columns() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("plugins.py")
+        .write_str(
+            r#"
+class Plugin:
+    priority = 0
+    abstract = True
+
+class S3Plugin(Plugin):
+    name = "s3"
+    priority = 5
+    abstract = False
+    priority = 10
+
+class S3MirrorPlugin(S3Plugin):
+    name = "s3-mirror"
+
+class DynamicPlugin(Plugin):
+    name = compute_name()
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Plugin")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--attr")
+        .arg("name")
+        .arg("--attr")
+        .arg("priority")
+        .arg("--attr")
+        .arg("abstract")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#"  S3Plugin (plugins) [name="s3" priority=10 abstract=False]"#,
+        ))
+        .stdout(predicate::str::contains(
+            r#"S3MirrorPlugin (plugins) [name="s3-mirror" priority=10 abstract=False]"#,
+        ))
+        .stdout(predicate::str::contains(
+            "DynamicPlugin (plugins) [name=<non-literal> priority=0 abstract=True]",
+        ));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Plugin")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--attr")
+        .arg("name")
+        .arg("--attr")
+        .arg("version")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#""name": {
+          "value": "s3-mirror",
+          "defined_in": "plugins.S3MirrorPlugin"
+        }"#,
+        ))
+        .stdout(predicate::str::contains(
+            r#""name": {
+          "value": null,
+          "defined_in": "plugins.DynamicPlugin"
+        }"#,
+        ))
+        .stdout(predicate::str::contains(
+            r#""version": {
+          "value": null,
+          "defined_in": null
+        }"#,
+        ));
+}
+
+#[test]
+fn test_fields() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("models.py")
+        .write_str(
+            r#"
+from dataclasses import dataclass
+from typing import ClassVar
+
+@dataclass
+class Base:
+    id: int
+    created: str = "now"
+    registry: ClassVar[dict] = {}
+
+    def save(self):
+        pass
+
+@dataclass
+class User(Base):
+    name: str = ""
+    created: str = "today"
+
+@dataclass
+class Admin(User):
+    level: int = 1
+
+class Tracked:
+    tracked_by: str
+
+@dataclass
+class Event(Base, Tracked):
+    kind: str = "click"
+
+class Guest(Base):
+    note: str = ""
+"#,
+        )
+        .unwrap();
+
+    temp.child("widgets.py")
+        .write_str(
+            r#"
+class Widget:
+    size: int
+
+class Button(Widget):
+    label: str
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--fields")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "User (models) [fields: id, created, name]",
+        ))
+        .stdout(predicate::str::contains(
+            "Admin (models) [fields: id, created, name, level]",
+        ))
+        // Only dataclass-like classes contribute fields
+        .stdout(predicate::str::contains(
+            "Event (models) [fields: id, created, kind]",
+        ))
+        .stdout(predicate::str::contains(
+            "Guest (models) [fields: id, created]",
+        ))
+        .stdout(predicate::str::contains("registry").not());
+
+    // Plain classes have no fields
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Widget")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--fields")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Button (widgets)"))
+        .stdout(predicate::str::contains("[fields:").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--fields")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""annotation": "int""#))
+        .stdout(predicate::str::contains(r#""default": "today""#))
+        .stdout(predicate::str::contains(r#""defined_in": "models.User""#))
+        .stdout(predicate::str::contains(r#""defined_in": "models.Admin""#))
+        .stdout(predicate::str::contains(r#""default": "now""#));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Base")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""fields": null"#));
+}
+
+#[test]
+fn test_enums() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("colors.py")
+        .write_str(
+            r#"
+import enum
+from enum import Enum, IntEnum, auto
+
+class Color(Enum):
+    _ignore_ = ["shades"]
+    RED = "red"
+    GREEN = "green"
+    BLUE = auto()
+    label: str
+
+    def describe(self):
+        return self.value
+
+class Priority(IntEnum):
+    LOW = 1
+    HIGH = 10
+
+class Permission(enum.Flag):
+    READ = 1
+    WRITE = 2
+
+class Palette:
+    RED = 1
+"#,
+        )
+        .unwrap();
+
+    temp.child("sizes.py")
+        .write_str(
+            r#"
+from enum import Enum
+
+class BaseEnum(Enum):
+    def describe(self):
+        return self.name
+
+class Size(BaseEnum):
+    SMALL = "s"
+    LARGE = "l"
+"#,
+        )
+        .unwrap();
+
+    // A class named `Enum` in the codebase doesn't restrict the listing of all enums
+    temp.child("widgets.py")
+        .write_str(
+            r#"
+class Enum:
+    pass
+
+class Kind(Enum):
+    BUTTON = 1
+"#,
+        )
+        .unwrap();
+
+    // Nor does a `Flag` class which isn't imported from the `enum` module
+    temp.child("features.py")
+        .write_str(
+            r#"
+from myapp.flags import Flag
+
+class Feature(Flag):
+    BETA = 1
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("--directory")
+        .arg(temp.path())
+        .arg("--enums")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 5 enum(s) deriving from 'enum.Enum':",
+        ))
+        .stdout(predicate::str::contains(
+            r#"Color (colors) [members: RED="red", GREEN="green", BLUE]"#,
+        ))
+        .stdout(predicate::str::contains(
+            "Priority (colors) [members: LOW=1, HIGH=10]",
+        ))
+        .stdout(predicate::str::contains(
+            r#"Size (sizes) [members: SMALL="s", LARGE="l"]"#,
+        ))
+        .stdout(predicate::str::contains("BaseEnum (sizes)"))
+        .stdout(predicate::str::contains("_ignore_").not())
+        .stdout(predicate::str::contains("Palette").not())
+        .stdout(predicate::str::contains("Kind").not())
+        .stdout(predicate::str::contains("Feature").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("IntEnum")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--enums")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 1 enum(s) deriving from 'IntEnum':",
+        ))
+        .stdout(predicate::str::contains("Priority (colors)"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    // `Flag` alone is ambiguous with `myapp.flags.Flag`
+    cmd.arg("enum.Flag")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--enums")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Permission (colors)"))
+        .stdout(predicate::str::contains("Color").not())
+        .stdout(predicate::str::contains("Feature").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("BaseEnum")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--enums")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 1 enum(s) deriving from 'BaseEnum':",
+        ))
+        .stdout(predicate::str::contains("Size (sizes)"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("IntEnum")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--enums")
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""enum_members": ["#))
+        .stdout(predicate::str::contains(r#""name": "HIGH""#))
+        .stdout(predicate::str::contains(r#""value": 10"#));
+
+    // Without --enums, a class name is required
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("--directory").arg(temp.path()).assert().failure();
+}
+
+#[test]
+fn test_external_classes() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("blog.py")
+        .write_str(
+            r#"
+from django.db import models
+from django.db.models import Model as DjangoModel
+
+Base = models.Model
+
+class Article(models.Model):
+    pass
+
+class Comment(DjangoModel):
+    pass
+
+class Tag(Base):
+    pass
+
+class FeaturedArticle(Article):
+    pass
+
+class PublishError(Exception):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--module")
+        .arg("django.db.models")
+        .arg("--directory")
+        .arg(temp.path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Found 4 subclass(es) of 'Model' (external class django.db.models.Model):",
+        ))
+        .stdout(predicate::str::contains("Article (blog)"))
+        .stdout(predicate::str::contains("Comment (blog)"))
+        .stdout(predicate::str::contains("Tag (blog)"))
+        .stdout(predicate::str::contains("FeaturedArticle (blog)"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("django.db.models.Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--mode")
+        .arg("direct")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 3 subclass(es)"))
+        .stdout(predicate::str::contains("FeaturedArticle").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Exception")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("json")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(r#""is_external": true"#))
+        .stdout(predicate::str::contains(r#""class_name": "PublishError""#));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--format")
+        .arg("dot")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            r#"django_db_models_Model [label="Model\n(django.db.models)", tooltip="external", fillcolor=lightgrey];"#,
+        ))
+        .stdout(predicate::str::contains(
+            "django_db_models_Model -> blog_Article;",
+        ));
+}
+
+#[test]
+fn test_library_roots() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("project/views.py")
+        .write_str(
+            r#"
+from rest_framework.views import APIView
+
+class MyView(APIView):
+    pass
+"#,
+        )
+        .unwrap();
+
+    let site_packages = temp.child("venv/lib/python3.12/site-packages");
+    // Installed packages are indexed even if ignored
+    site_packages.child(".gitignore").write_str("*\n").unwrap();
+    site_packages
+        .child("django/__init__.py")
+        .write_str("")
+        .unwrap();
+    site_packages
+        .child("django/views/__init__.py")
+        .write_str("from django.views.generic.base import View\n")
+        .unwrap();
+    site_packages
+        .child("django/views/generic/__init__.py")
+        .write_str("")
+        .unwrap();
+    site_packages
+        .child("django/views/generic/base.py")
+        .write_str("class View:\n    pass\n")
+        .unwrap();
+    site_packages
+        .child("rest_framework/__init__.py")
+        .write_str("")
+        .unwrap();
+    site_packages
+        .child("rest_framework/views.py")
+        .write_str("from django.views import View\n\nclass APIView(View):\n    pass\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("View")
+        .arg("--module")
+        .arg("django.views.generic.base")
+        .arg("--directory")
+        .arg(temp.child("project").path())
+        .arg("--venv")
+        .arg(temp.child("venv").path())
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 1 subclass(es) of 'View':"))
+        .stdout(predicate::str::contains("MyView (views)"))
+        .stdout(predicate::str::contains("APIView").not());
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("View")
+        .arg("--directory")
+        .arg(temp.child("project").path())
+        .arg("--site-packages")
+        .arg(site_packages.path())
+        .arg("--include-library")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 2 subclass(es) of 'View':"))
+        .stdout(predicate::str::contains(
+            "APIView (rest_framework.views) [library]",
+        ))
+        .stdout(predicate::str::contains("MyView (views) views.py"));
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("View")
+        .arg("--directory")
+        .arg(temp.child("project").path())
+        .arg("--venv")
+        .arg(temp.child("project").path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No site-packages directory found"));
+}
+
+#[test]
+fn test_source_roots() {
+    let temp = assert_fs::TempDir::new().unwrap();
+
+    temp.child("src/shop/__init__.py").write_str("").unwrap();
+    temp.child("src/shop/models.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+    temp.child("src/shop/orders.py")
+        .write_str("from shop.models import Model\n\nclass Order(Model):\n    pass\n")
+        .unwrap();
+    temp.child("services/billing/src/billing/__init__.py")
+        .write_str("")
+        .unwrap();
+    temp.child("services/billing/src/billing/invoices.py")
+        .write_str("from shop.models import Model\n\nclass Invoice(Model):\n    pass\n")
+        .unwrap();
+    temp.child("services/legacy/src/shop/models.py")
+        .write_str("class Model:\n    pass\n")
+        .unwrap();
+    temp.child("tests/test_orders.py")
+        .write_str("from shop.orders import Order\n\nclass FakeOrder(Order):\n    pass\n")
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--module")
+        .arg("src/shop/models.py")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--source-root")
+        .arg("src")
+        .arg("--source-root")
+        .arg("services/billing/src")
+        .arg("--source-root")
+        .arg("services/legacy/src")
+        .env("RUST_LOG", "warn")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Found 3 subclass(es) of 'Model':"))
+        .stdout(predicate::str::contains("Order (shop.orders)"))
+        .stdout(predicate::str::contains("Invoice (billing.invoices)"))
+        .stdout(predicate::str::contains("FakeOrder (tests.test_orders)"))
+        .stderr(predicate::str::contains(
+            "Module 'shop.models' is provided by both src/shop/models.py and services/legacy/src/shop/models.py",
+        ));
+
+    // Without source roots, module paths are relative to the search directory
+    let mut cmd = Command::cargo_bin("pysubclasses").unwrap();
+    cmd.arg("Model")
+        .arg("--module")
+        .arg("src.shop.models")
+        .arg("--directory")
+        .arg(temp.path())
+        .arg("--no-cache")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No subclasses found for 'Model'"));
+}